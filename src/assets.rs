@@ -0,0 +1,97 @@
+//! Locates the files the game loads at runtime.
+//!
+//! Assets are resolved relative to a root directory, which is chosen from (in order):
+//! the `--assets` command line option, the `RUST_BELT_ASSETS` environment variable,
+//! an `assets` directory next to the executable, and finally an `assets` directory
+//! in the working directory.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that can point at the asset root.
+pub const ROOT_ENV_VAR: &str = "RUST_BELT_ASSETS";
+
+pub const FONT: &str = "fonts/FiraSans-Regular.ttf";
+pub const LOGO: &str = "images/rust-belt-logo-transparent.png";
+
+pub const MENU_MUSIC: &str = "music/The Last Ranger.mp3";
+pub const ACTION_MUSIC: &str = "music/Into the Field.mp3";
+pub const GAME_OVER_MUSIC: &str = "music/Splintered Glass.mp3";
+
+pub const MENU_SELECT_SOUND: &str = "sfx/menu-select.wav";
+pub const MENU_BACK_SOUND: &str = "sfx/menu-back.wav";
+pub const MENU_VALIDATE_SOUND: &str = "sfx/menu-validate.wav";
+pub const WEAPON_SOUND: &str = "sfx/weapon.wav";
+pub const EXPLOSION_SOUND: &str = "sfx/small-explosion.wav";
+pub const HYPERSPACE_SOUND: &str = "sfx/hyperspace.wav";
+pub const CHARGED_SHOT_SOUND: &str = "sfx/charged-shot.wav";
+pub const LASER_SOUND: &str = "sfx/laser.wav";
+
+/// Every file that must be present for the game to run.
+const REQUIRED: &[&str] = &[
+    FONT,
+    LOGO,
+    MENU_MUSIC,
+    ACTION_MUSIC,
+    GAME_OVER_MUSIC,
+    MENU_SELECT_SOUND,
+    MENU_BACK_SOUND,
+    MENU_VALIDATE_SOUND,
+    WEAPON_SOUND,
+    EXPLOSION_SOUND,
+    HYPERSPACE_SOUND,
+    CHARGED_SHOT_SOUND,
+    LASER_SOUND,
+];
+
+/// The name of the asset directory searched for next to the executable and in the working directory.
+const DEFAULT_DIR: &str = "assets";
+
+/// Resolves asset names to paths within the asset root.
+pub struct Assets {
+    root: PathBuf,
+}
+
+impl Assets {
+    /// Chooses the asset root. An explicitly requested root (from the command line or
+    /// the environment) is always used as is. Otherwise the first default location that
+    /// exists is used.
+    pub fn locate(requested_root: Option<PathBuf>) -> Self {
+        let root = requested_root
+            .or_else(|| env::var_os(ROOT_ENV_VAR).map(PathBuf::from))
+            .unwrap_or_else(|| {
+                let next_to_exe = env::current_exe()
+                    .ok()
+                    .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_DIR)));
+                next_to_exe
+                    .into_iter()
+                    .chain([PathBuf::from(DEFAULT_DIR)])
+                    .find(|dir| dir.is_dir())
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR))
+            });
+        Assets { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The full path of an asset, given its path relative to the root.
+    pub fn path(&self, asset: &str) -> PathBuf {
+        self.root.join(asset)
+    }
+
+    /// Checks that every required asset exists, returning the paths of all that do not.
+    pub fn verify(&self) -> Result<(), Vec<PathBuf>> {
+        let missing: Vec<PathBuf> = REQUIRED
+            .iter()
+            .map(|asset| self.path(asset))
+            .filter(|path| !path.is_file())
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+}
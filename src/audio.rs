@@ -0,0 +1,149 @@
+//! Music and sound effects.
+//!
+//! All audio is played through an `AudioSink`, so the rest of the game does not
+//! depend on an audio device being present. `SdlAudio` plays through SDL2 and
+//! `NullAudio` silently discards everything.
+
+pub use music::{MAX_VOLUME, MIN_VOLUME};
+
+use crate::assets::{self, Assets};
+
+/// The different music soundtrack pieces in the game.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Music {
+    /// Menu soundtrack.
+    Menu,
+
+    /// Action soundtrack while playing the actual game.
+    Action,
+
+    /// Game over soundtrack.
+    GameOver,
+}
+
+/// Sound effects.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Sound {
+    MenuSelection,
+    MenuBack,
+    MenuValidate,
+    WeaponShoot,
+    AsteroidExplosion,
+    Hyperspace,
+    ChargedShot,
+    Laser,
+}
+
+/// Volume for music and sound effects.
+#[derive(Copy, Clone)]
+pub struct Volume {
+    pub music: f64,
+    pub sound: f64,
+}
+
+impl Volume {
+    /// Sound effects start at half volume so they do not drown out the music.
+    pub fn new() -> Self {
+        Volume {
+            music: MAX_VOLUME,
+            sound: MAX_VOLUME / 2.0,
+        }
+    }
+}
+
+/// Something that can play music and sound effects.
+pub trait AudioSink {
+    /// Plays a sound effect once at the given volume.
+    fn play_sound(&mut self, sound: Sound, volume: f64);
+
+    /// Loops a soundtrack forever, replacing the one currently playing.
+    fn play_music(&mut self, music: Music);
+
+    /// Sets the volume of the soundtrack.
+    fn set_music_volume(&mut self, volume: f64);
+}
+
+/// Plays audio through SDL2 using piston-music.
+///
+/// Must only be created and used within `music::start_context`.
+pub struct SdlAudio;
+
+impl SdlAudio {
+    /// The number of sound effects that can play at the same time.
+    pub const SOUND_CHANNELS: i32 = 32;
+
+    /// Binds sound and music files to enums to be used with piston-music.
+    pub fn new(assets: &Assets) -> Self {
+        music::bind_music_file(Music::Menu, assets.path(assets::MENU_MUSIC));
+        music::bind_music_file(Music::Action, assets.path(assets::ACTION_MUSIC));
+        music::bind_music_file(Music::GameOver, assets.path(assets::GAME_OVER_MUSIC));
+
+        music::bind_sound_file(Sound::MenuSelection, assets.path(assets::MENU_SELECT_SOUND));
+        music::bind_sound_file(Sound::MenuBack, assets.path(assets::MENU_BACK_SOUND));
+        music::bind_sound_file(
+            Sound::MenuValidate,
+            assets.path(assets::MENU_VALIDATE_SOUND),
+        );
+        music::bind_sound_file(Sound::WeaponShoot, assets.path(assets::WEAPON_SOUND));
+        music::bind_sound_file(
+            Sound::AsteroidExplosion,
+            assets.path(assets::EXPLOSION_SOUND),
+        );
+        music::bind_sound_file(Sound::Hyperspace, assets.path(assets::HYPERSPACE_SOUND));
+        music::bind_sound_file(Sound::ChargedShot, assets.path(assets::CHARGED_SHOT_SOUND));
+        music::bind_sound_file(Sound::Laser, assets.path(assets::LASER_SOUND));
+        SdlAudio
+    }
+}
+
+impl AudioSink for SdlAudio {
+    fn play_sound(&mut self, sound: Sound, volume: f64) {
+        music::play_sound(&sound, music::Repeat::Times(0), volume);
+    }
+
+    fn play_music(&mut self, track: Music) {
+        music::play_music(&track, music::Repeat::Forever);
+    }
+
+    fn set_music_volume(&mut self, volume: f64) {
+        music::set_volume(volume);
+    }
+}
+
+/// Discards all audio, for machines without an audio device.
+pub struct NullAudio;
+
+impl AudioSink for NullAudio {
+    fn play_sound(&mut self, _sound: Sound, _volume: f64) {}
+
+    fn play_music(&mut self, _music: Music) {}
+
+    fn set_music_volume(&mut self, _volume: f64) {}
+}
+
+/// Records all audio instead of playing it, so tests can check what was triggered.
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingAudio {
+    /// Every sound effect played, in order, with the volume it was played at.
+    pub sounds: Vec<(Sound, f64)>,
+
+    /// Every soundtrack started, in order.
+    pub music: Vec<Music>,
+    pub music_volume: Option<f64>,
+}
+
+#[cfg(test)]
+impl AudioSink for RecordingAudio {
+    fn play_sound(&mut self, sound: Sound, volume: f64) {
+        self.sounds.push((sound, volume));
+    }
+
+    fn play_music(&mut self, music: Music) {
+        self.music.push(music);
+    }
+
+    fn set_music_volume(&mut self, volume: f64) {
+        self.music_volume = Some(volume);
+    }
+}
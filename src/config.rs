@@ -0,0 +1,374 @@
+//! Saves and loads the player's settings.
+//!
+//! Settings are stored as plain text in the platform's config directory, such as
+//! `~/.config/rust-belt/settings.txt` on Linux:
+//!
+//! ```text
+//! rust-belt-settings 1
+//! music-volume 1
+//! sound-volume 0.5
+//! mode Endless
+//! difficulty Normal
+//! lives 3
+//! score-large 20
+//! score-medium 50
+//! score-small 100
+//! extra-life 10000
+//! fullscreen false
+//! resolution 1024x768
+//! vsync false
+//! samples 4
+//! max-fps 60
+//! ups 120
+//! key-rotate-cw D
+//! key-rotate-ccw A
+//! key-thrust W
+//! key-reverse-thrust S
+//! key-shoot Space
+//! key-hyperspace LShift
+//! key-switch-weapon Q
+//! ```
+//!
+//! Settings that are missing or cannot be read keep their default value, so an old
+//! or damaged file never stops the game from starting.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use piston_window::Key;
+
+use crate::audio::{self, Volume};
+use crate::game::controls::Controls;
+use crate::game::state::{self, Difficulty, Mode, Rules};
+use crate::video::{self, Video};
+
+/// Identifies a settings file and the version of the file format.
+const MAGIC: &str = "rust-belt-settings 1";
+
+/// Keys that can be bound to the player's actions.
+const BINDABLE_KEYS: &[Key] = &[
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::D0,
+    Key::D1,
+    Key::D2,
+    Key::D3,
+    Key::D4,
+    Key::D5,
+    Key::D6,
+    Key::D7,
+    Key::D8,
+    Key::D9,
+    Key::Space,
+    Key::Return,
+    Key::Tab,
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::LShift,
+    Key::RShift,
+    Key::LCtrl,
+    Key::RCtrl,
+    Key::LAlt,
+    Key::RAlt,
+];
+
+/// Every setting the player can change.
+pub struct Config {
+    pub volume: Volume,
+    pub rules: Rules,
+    pub video: Video,
+    pub controls: Controls,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            volume: Volume::new(),
+            rules: Rules::new(),
+            video: Video::new(),
+            controls: Controls::new(),
+        }
+    }
+
+    /// Where the settings are saved, if the platform has a config directory.
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rust-belt").join("settings.txt"))
+    }
+
+    /// Loads the saved settings, falling back to the defaults for anything that
+    /// has not been saved or cannot be read.
+    pub fn load() -> Self {
+        let Some(path) = Config::path() else {
+            return Config::new();
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&contents).unwrap_or_else(|error| {
+                eprintln!("Ignoring settings in '{}': {error}", path.display());
+                Config::new()
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Config::new(),
+            Err(error) => {
+                eprintln!("Failed to read settings '{}': {error}", path.display());
+                Config::new()
+            }
+        }
+    }
+
+    /// Saves the settings so they are loaded the next time the game starts.
+    pub fn save(&self) -> io::Result<()> {
+        let path = Config::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write to a temporary file first so a crash can never leave a half written file.
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_text())?;
+        fs::rename(&temporary, &path)
+    }
+
+    fn to_text(&self) -> String {
+        let (video, controls) = (&self.video, &self.controls);
+        format!(
+            "{MAGIC}\n\
+             music-volume {}\n\
+             sound-volume {}\n\
+             mode {}\n\
+             difficulty {}\n\
+             lives {}\n\
+             score-large {}\n\
+             score-medium {}\n\
+             score-small {}\n\
+             extra-life {}\n\
+             fullscreen {}\n\
+             resolution {}x{}\n\
+             vsync {}\n\
+             samples {}\n\
+             max-fps {}\n\
+             ups {}\n\
+             key-rotate-cw {:?}\n\
+             key-rotate-ccw {:?}\n\
+             key-thrust {:?}\n\
+             key-reverse-thrust {:?}\n\
+             key-shoot {:?}\n\
+             key-hyperspace {:?}\n\
+             key-switch-weapon {:?}\n",
+            self.volume.music,
+            self.volume.sound,
+            self.rules.mode.name(),
+            self.rules.difficulty.name(),
+            self.rules.lives,
+            self.rules.scores.large,
+            self.rules.scores.medium,
+            self.rules.scores.small,
+            self.rules.extra_life,
+            video.fullscreen,
+            video.resolution[0],
+            video.resolution[1],
+            video.vsync,
+            video.samples,
+            video.max_fps,
+            video.ups,
+            controls.rotate_cw,
+            controls.rotate_ccw,
+            controls.thrust,
+            controls.reverse_thrust,
+            controls.shoot,
+            controls.hyperspace,
+            controls.switch_weapon,
+        )
+    }
+
+    /// Reads settings from the contents of a settings file. Fails only if the file is
+    /// not a settings file at all. Invalid settings are reported and left at their default.
+    fn parse(contents: &str) -> Result<Config, String> {
+        let mut lines = contents.lines();
+        if lines.next() != Some(MAGIC) {
+            return Err("not a settings file".to_string());
+        }
+
+        let mut config = Config::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            if !config.apply(name, value.trim()) {
+                eprintln!("Ignoring invalid setting '{line}'");
+            }
+        }
+        Ok(config)
+    }
+
+    /// Changes the setting called `name` to `value`. Returns whether it was valid.
+    fn apply(&mut self, name: &str, value: &str) -> bool {
+        let (rules, video, controls) = (&mut self.rules, &mut self.video, &mut self.controls);
+        let updated = match name {
+            "music-volume" => parse_volume(value).map(|volume| self.volume.music = volume),
+            "sound-volume" => parse_volume(value).map(|volume| self.volume.sound = volume),
+            "mode" => Mode::from_name(value).map(|mode| rules.mode = mode),
+            "difficulty" => Difficulty::from_name(value).map(|level| rules.difficulty = level),
+            "lives" => parse_lives(value).map(|lives| rules.lives = lives),
+            "score-large" => parse_points(value).map(|points| rules.scores.large = points),
+            "score-medium" => parse_points(value).map(|points| rules.scores.medium = points),
+            "score-small" => parse_points(value).map(|points| rules.scores.small = points),
+            "extra-life" => parse_extra_life(value).map(|score| rules.extra_life = score),
+            "fullscreen" => value.parse().ok().map(|enabled| video.fullscreen = enabled),
+            "resolution" => parse_resolution(value).map(|size| video.resolution = size),
+            "vsync" => value.parse().ok().map(|enabled| video.vsync = enabled),
+            "samples" => parse_samples(value).map(|samples| video.samples = samples),
+            "max-fps" => parse_rate(value, video::FPS_CAPS).map(|rate| video.max_fps = rate),
+            "ups" => parse_rate(value, video::UPS_RATES).map(|rate| video.ups = rate),
+            "key-rotate-cw" => parse_key(value).map(|key| controls.rotate_cw = key),
+            "key-rotate-ccw" => parse_key(value).map(|key| controls.rotate_ccw = key),
+            "key-thrust" => parse_key(value).map(|key| controls.thrust = key),
+            "key-reverse-thrust" => parse_key(value).map(|key| controls.reverse_thrust = key),
+            "key-shoot" => parse_key(value).map(|key| controls.shoot = key),
+            "key-hyperspace" => parse_key(value).map(|key| controls.hyperspace = key),
+            "key-switch-weapon" => parse_key(value).map(|key| controls.switch_weapon = key),
+            _ => None,
+        };
+        updated.is_some()
+    }
+}
+
+fn parse_volume(value: &str) -> Option<f64> {
+    let volume: f64 = value.parse().ok()?;
+    (audio::MIN_VOLUME..=audio::MAX_VOLUME)
+        .contains(&volume)
+        .then_some(volume)
+}
+
+fn parse_lives(value: &str) -> Option<u32> {
+    value
+        .parse()
+        .ok()
+        .filter(|lives| state::STARTING_LIVES.contains(lives))
+}
+
+fn parse_points(value: &str) -> Option<i64> {
+    value.parse().ok().filter(|&points| points >= 0)
+}
+
+/// Reads the score needed for each extra life, which must be positive.
+fn parse_extra_life(value: &str) -> Option<i64> {
+    value.parse().ok().filter(|&score| score > 0)
+}
+
+fn parse_resolution(value: &str) -> Option<[u32; 2]> {
+    let (width, height) = value.split_once('x')?;
+    let size = [width.parse().ok()?, height.parse().ok()?];
+    video::RESOLUTIONS.contains(&size).then_some(size)
+}
+
+fn parse_samples(value: &str) -> Option<u8> {
+    value
+        .parse()
+        .ok()
+        .filter(|samples| video::SAMPLES.contains(samples))
+}
+
+/// Reads a rate that must be one of `presets`.
+fn parse_rate(value: &str, presets: &[u64]) -> Option<u64> {
+    value.parse().ok().filter(|rate| presets.contains(rate))
+}
+
+fn parse_key(value: &str) -> Option<Key> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| format!("{key:?}") == value)
+}
+
+#[cfg(test)]
+mod tests {
+    use piston_window::Key;
+
+    use super::{Config, MAGIC};
+    use crate::game::state::{Difficulty, Mode};
+
+    /// Parses a settings file made of the given lines after the magic line.
+    fn parse_lines(lines: &str) -> Config {
+        Config::parse(&format!("{MAGIC}\n{lines}")).unwrap()
+    }
+
+    #[test]
+    fn saved_settings_load_the_same() {
+        let mut config = Config::new();
+        config.volume.music = 0.25;
+        config.volume.sound = 0.75;
+        config.rules.mode = Mode::Waves;
+        config.rules.difficulty = Difficulty::Hard;
+        config.rules.lives = 5;
+        config.rules.scores.large = 30;
+        config.rules.extra_life = 20000;
+        config.video.fullscreen = true;
+        config.video.resolution = [1920, 1080];
+        config.video.samples = 8;
+        config.video.max_fps = 144;
+        config.video.ups = 240;
+        config.controls.hyperspace = Key::RShift;
+        config.controls.switch_weapon = Key::Tab;
+
+        let loaded = Config::parse(&config.to_text()).unwrap();
+        assert_eq!(loaded.volume.music, 0.25);
+        assert_eq!(loaded.volume.sound, 0.75);
+        assert_eq!(loaded.rules, config.rules);
+        assert!(loaded.video == config.video);
+        assert!(loaded.controls == config.controls);
+    }
+
+    #[test]
+    fn invalid_settings_keep_their_default() {
+        let defaults = Config::new();
+        let config = parse_lines(
+            "music-volume 1.5\n\
+             sound-volume -0.1\n\
+             speed 11\n\
+             resolution 1000x700\n\
+             samples 3\n\
+             max-fps 75\n\
+             ups 100\n\
+             key-shoot Escape\n",
+        );
+        assert_eq!(config.volume.music, defaults.volume.music);
+        assert_eq!(config.volume.sound, defaults.volume.sound);
+        assert!(config.video == defaults.video);
+        assert!(config.controls == defaults.controls);
+
+        // Valid settings after the invalid ones are still read.
+        let config = parse_lines("speed 11\nlives 1\n");
+        assert_eq!(config.rules.lives, 1);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(Config::parse("rust-belt-settings 0\nlives 1\n").is_err());
+        assert!(Config::parse("").is_err());
+    }
+}
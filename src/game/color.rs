@@ -6,7 +6,9 @@ pub const BLACK: types::Color = [0.0, 0.0, 0.0, 1.0];
 pub const WHITE: types::Color = [1.0, 1.0, 1.0, 1.0];
 pub const CYAN: types::Color = [0.0, 1.0, 1.0, 1.0];
 pub const YELLOW: types::Color = [1.0, 1.0, 0.0, 1.0];
+pub const GREEN: types::Color = [0.0, 1.0, 0.0, 1.0];
 pub const MAGENTA: types::Color = [1.0, 0.0, 1.0, 1.0];
+pub const GRAY: types::Color = [0.5, 0.5, 0.5, 1.0];
 pub const DIM_RED: types::Color = [1.0, 0.0, 0.0, 0.5];
 
 /// A type for storing text and an associated color it should
@@ -0,0 +1,56 @@
+//! Keyboard bindings for flying the ship.
+
+use piston_window::Key;
+
+use super::models::player::Actions;
+
+/// The key bound to each of the player's actions.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Controls {
+    pub rotate_cw: Key,
+    pub rotate_ccw: Key,
+    pub thrust: Key,
+    pub reverse_thrust: Key,
+    pub shoot: Key,
+    pub hyperspace: Key,
+    pub switch_weapon: Key,
+}
+
+impl Controls {
+    pub fn new() -> Self {
+        Controls {
+            rotate_cw: Key::D,
+            rotate_ccw: Key::A,
+            thrust: Key::W,
+            reverse_thrust: Key::S,
+            shoot: Key::Space,
+            hyperspace: Key::LShift,
+            switch_weapon: Key::Q,
+        }
+    }
+
+    /// Updates the action bound to `key`, if any, to whether the key is held down.
+    pub fn apply(&self, key: Key, held: bool, actions: &mut Actions) {
+        if key == self.rotate_cw {
+            actions.rotate_cw = held;
+        }
+        if key == self.rotate_ccw {
+            actions.rotate_ccw = held;
+        }
+        if key == self.thrust {
+            actions.fire_boosters = held;
+        }
+        if key == self.reverse_thrust {
+            actions.fire_rev_boosters = held;
+        }
+        if key == self.shoot {
+            actions.is_shooting = held;
+        }
+        if key == self.hyperspace {
+            actions.hyperspace = held;
+        }
+        if key == self.switch_weapon {
+            actions.switch_weapon = held;
+        }
+    }
+}
@@ -1,276 +1,448 @@
 //! Defines the game component.
 //!
-//! `Game` is the primary type that holds information about the
-//! current state of the game and the objects that exist within it.
+//! `Game` is the thin screen around a `GameState` that connects it to
+//! the outside world. It takes user input from the keyboard in order to
+//! control the `Player`, steps the simulation, plays sounds for anything
+//! that happened and draws the current state to the window.
 //!
-//! `Game` takes user input from the keyboard in order to control the `Player`
-//! and handles collision detection and the TTL for `Bullet`s.
+//! Every game is recorded as a `Replay`, and a `Game` can also be created from
+//! a `Replay` to play it back in place of the keyboard.
+
+use std::fmt::Debug;
 
 use opengl_graphics::{GlGraphics, GlyphCache};
+use piston_window::character::CharacterCache;
 use piston_window::{
-    clear, text, Button, Context, Key, PistonWindow, PressEvent, ReleaseEvent, RenderEvent, Size,
-    Transformed, UpdateArgs, UpdateEvent,
+    clear, rectangle, text, Button, ButtonArgs, ButtonState, Context, Graphics, Key, Rectangle,
+    Size, Transformed, UpdateArgs,
 };
 
-use self::models::{asteroid, bullet, player, Collidable, Drawable, Updateable};
-use crate::menu::{Sound, Volume};
+use self::models::weapon::WeaponKind;
+use self::models::{player, Drawable};
+use self::replay::{Playback, Replay};
+use self::state::{GameEvent, GameState, Rules, TIMESTEP};
+use crate::audio::{Music, Sound};
+use crate::screen::{Resources, Screen, Transition};
 
 pub mod color;
+pub mod controls;
 mod models;
+pub mod replay;
+mod spatial_hash;
+pub mod state;
+
+/// The most time in seconds that will be simulated for a single update event.
+const MAX_FRAME_TIME: f64 = 0.25;
 
-/// Stores Game state and all objects that exist.
+/// The width of the shield's energy bar in the HUD when full.
+const ENERGY_BAR_WIDTH: f64 = 100.0;
+
+/// Connects the game simulation to the window, keyboard and speakers.
 pub struct Game {
-    player: player::Player,
+    state: GameState,
 
-    /// The bullets that are currently live in the window.
-    /// Bullets are removed when their TTL expires.
-    bullets: Vec<bullet::Bullet>,
-    asteroids: Vec<asteroid::Asteroid>,
-    score: i64,
-    window_size: Size,
-    asteroid_timer: f64,
-    asteroid_timer_max: f64,
+    /// The user actions currently held down, fed into the simulation every step.
+    actions: player::Actions,
+
+    /// Time in seconds that has passed but not yet been simulated.
+    accumulator: f64,
+
+    /// The number of simulation steps taken so far.
+    tick: u64,
 
-    /// A flag indicating if the player has lost.
-    /// This should not be set if the player simply quits.
-    game_over: bool,
-    volume: Volume,
+    /// The recording of the inputs of this game.
+    recording: Replay,
+
+    /// Present if this game is playing back a replay rather than taking keyboard input.
+    playback: Option<Playback>,
 }
 
 impl Game {
-    pub fn new(window_size: Size, volume: Volume) -> Self {
+    pub fn new(bounds: Size, seed: u64, rules: Rules) -> Self {
         Game {
-            player: player::Player::new(window_size),
-            bullets: Vec::new(),
-            asteroids: Vec::new(),
-            score: 0,
-            window_size,
-            asteroid_timer: 0.1,
-            asteroid_timer_max: 4.0,
-            game_over: false,
-            volume,
+            state: GameState::new(bounds, seed, rules),
+            actions: player::Actions::default(),
+            accumulator: 0.0,
+            tick: 0,
+            recording: Replay::new(seed, rules),
+            playback: None,
         }
     }
 
-    pub fn game_over(&self) -> bool {
-        self.game_over
+    /// Creates a game that plays back `replay` instead of reacting to the keyboard.
+    pub fn from_replay(bounds: Size, replay: Replay) -> Self {
+        let mut game = Game::new(bounds, replay.seed(), replay.rules());
+        game.playback = Some(Playback::new(replay));
+        game
     }
 
-    pub fn run(
-        &mut self,
-        window: &mut PistonWindow,
-        opengl: &mut GlGraphics,
-        glyph_cache: &mut GlyphCache<'_>,
-    ) {
-        for event in window.by_ref() {
-            if let Some(args) = event.render_args() {
-                opengl.draw(args.viewport(), |context, graphics| {
-                    self.draw(context, graphics, glyph_cache)
-                });
-            }
+    fn playback_finished(&self) -> bool {
+        self.playback
+            .as_ref()
+            .is_some_and(|playback| playback.is_finished(self.tick))
+    }
 
-            if let Some(args) = event.update_args() {
-                self.update(args);
+    /// Saves the recording of the game the user played. Replays are not recorded again.
+    fn save_recording(&self) {
+        if self.playback.is_none() {
+            if let Err(error) = self.recording.save_new() {
+                eprintln!("Failed to save replay: {error}");
             }
+        }
+    }
 
-            if let Some(Button::Keyboard(key)) = event.press_args() {
-                match key {
-                    Key::D => self.player.actions.rotate_cw = true,
-                    Key::A => self.player.actions.rotate_ccw = true,
-                    Key::S => self.player.actions.fire_rev_boosters = true,
-                    Key::W => self.player.actions.fire_boosters = true,
-                    Key::Space => self.player.actions.is_shooting = true,
-                    Key::X => {
-                        music::play_sound(
-                            &Sound::MenuBack,
-                            music::Repeat::Times(0),
-                            self.volume.sound,
-                        );
-                        break;
-                    }
-                    _ => {}
-                }
+    /// Draws all current live objects as well as the current score and lives.
+    /// Works with any graphics back-end and font cache, not only the window's.
+    pub fn draw<G, C>(&self, context: Context, graphics: &mut G, glyph_cache: &mut C)
+    where
+        G: Graphics,
+        C: CharacterCache<Texture = G::Texture>,
+        C::Error: Debug,
+    {
+        let bounds = self.state.bounds();
+        clear(color::BLACK, graphics);
+        for bullet in self.state.bullets() {
+            bullet.draw_wrapped(context, graphics, bounds);
+        }
+        if let Some(beam) = self.state.beam() {
+            beam.draw_wrapped(context, graphics, bounds);
+        }
+        if let Some(player) = self.state.player() {
+            player.draw_wrapped(context, graphics, bounds);
+        }
+        for asteroid in self.state.asteroids() {
+            asteroid.draw_wrapped(context, graphics, bounds);
+        }
+        for saucer in self.state.saucers() {
+            saucer.draw_wrapped(context, graphics, bounds);
+        }
+        for bullet in self.state.saucer_bullets() {
+            bullet.draw_wrapped(context, graphics, bounds);
+        }
+        for powerup in self.state.powerups() {
+            powerup.draw_wrapped(context, graphics, bounds);
+        }
+        for popup in self.state.popups() {
+            popup.draw(context, graphics, glyph_cache);
+        }
+
+        let score = format!("Score: {}", self.state.score());
+        text(
+            color::YELLOW,
+            26,
+            &score,
+            glyph_cache,
+            context.transform.trans(10.0, 20.0),
+            graphics,
+        )
+        .unwrap();
+        if self.state.multiplier() > 1 {
+            let score_width = glyph_cache.width(26, &score).unwrap();
+            text(
+                color::MAGENTA,
+                26,
+                &format!("x{}", self.state.multiplier()),
+                glyph_cache,
+                context.transform.trans(30.0 + score_width, 20.0),
+                graphics,
+            )
+            .unwrap();
+        }
+        for life in 0..self.state.lives() {
+            player::Player::draw_life_icon(
+                context.trans(16.0 + f64::from(life) * 18.0, 52.0),
+                graphics,
+            );
+        }
+        if let Some(player) = self.state.player() {
+            // The shield's energy bar, with a border showing how full it can get.
+            let energy_bar = [10.0, 62.0, ENERGY_BAR_WIDTH, 6.0];
+            Rectangle::new_border(color::GRAY, 0.5).draw(
+                energy_bar,
+                &context.draw_state,
+                context.transform,
+                graphics,
+            );
+            rectangle(
+                color::CYAN,
+                [
+                    energy_bar[0],
+                    energy_bar[1],
+                    ENERGY_BAR_WIDTH * player.energy() / player::MAX_ENERGY,
+                    energy_bar[3],
+                ],
+                context.transform,
+                graphics,
+            );
+
+            for (index, (kind, remaining)) in player.effects().active().enumerate() {
+                text(
+                    kind.color(),
+                    16,
+                    &format!("{} {:.1}", kind.name(), remaining),
+                    glyph_cache,
+                    context.transform.trans(10.0, 90.0 + index as f64 * 20.0),
+                    graphics,
+                )
+                .unwrap();
             }
 
-            if let Some(Button::Keyboard(key)) = event.release_args() {
-                match key {
-                    Key::D => self.player.actions.rotate_cw = false,
-                    Key::A => self.player.actions.rotate_ccw = false,
-                    Key::S => self.player.actions.fire_rev_boosters = false,
-                    Key::W => self.player.actions.fire_boosters = false,
-                    Key::Space => self.player.actions.is_shooting = false,
-                    _ => {}
+            // The weapon in use, with how far it has charged while charging.
+            let weapon = match player.charge() {
+                charge if charge > 0.0 => {
+                    format!("{} {}%", player.weapon().name(), (charge * 100.0) as u32)
                 }
-            }
+                _ => player.weapon().name().to_string(),
+            };
+            text(
+                color::WHITE,
+                16,
+                &weapon,
+                glyph_cache,
+                context.transform.trans(10.0, bounds.height - 10.0),
+                graphics,
+            )
+            .unwrap();
+        }
 
-            if self.game_over {
-                break;
-            }
+        if let Some(sector) = self.state.cleared_sector() {
+            text(
+                color::WHITE,
+                40,
+                &format!("Sector {sector} cleared"),
+                glyph_cache,
+                context
+                    .transform
+                    .trans(bounds.width / 2.0 - 170.0, bounds.height / 2.0 - 100.0),
+                graphics,
+            )
+            .unwrap();
+        }
+
+        if self.playback.is_some() {
+            text(
+                color::WHITE,
+                26,
+                "Replay",
+                glyph_cache,
+                context.transform.trans(bounds.width - 100.0, 20.0),
+                graphics,
+            )
+            .unwrap();
         }
     }
 
-    /// Game over screen logic.
-    pub fn run_game_over(
-        &self,
-        window: &mut PistonWindow,
-        opengl: &mut GlGraphics,
-        glyph_cache: &mut GlyphCache<'_>,
-    ) {
-        // Wait for the player to have pressed and release a key before
-        // continuing in case they were holding a button down during
-        // game over.
-        let mut has_pressed = false;
-        let mut has_released = false;
-        for event in window.by_ref() {
-            if let Some(args) = event.render_args() {
-                opengl.draw(args.viewport(), |context, graphics| {
-                    clear(color::BLACK, graphics);
-                    text(
-                        color::WHITE,
-                        50,
-                        "Game Over",
-                        glyph_cache,
-                        context.transform.trans(
-                            self.window_size.width / 2.0 - 120.0,
-                            self.window_size.height / 2.0 - 30.0,
-                        ),
-                        graphics,
-                    )
-                    .unwrap();
-                    let offset = (self.score.to_string().len() * 5) as f64;
-                    text(
-                        color::WHITE,
-                        50,
-                        format!("Score: {}", self.score).as_str(),
-                        glyph_cache,
-                        context.transform.trans(
-                            self.window_size.width / 2.0 - 90.0 - offset,
-                            self.window_size.height / 2.0 + 30.0,
-                        ),
-                        graphics,
-                    )
-                    .unwrap();
-                });
-            }
+    /// Plays the sound effect associated with each simulation event through `play`.
+    fn play_sounds<F: FnMut(Sound)>(events: &[GameEvent], mut play: F) {
+        for event in events {
+            let sound = match event {
+                GameEvent::WeaponShoot(WeaponKind::ChargedShot) => Sound::ChargedShot,
+                GameEvent::WeaponShoot(WeaponKind::Laser) => Sound::Laser,
+                GameEvent::WeaponShoot(_) => Sound::WeaponShoot,
+                GameEvent::SaucerShoot => Sound::WeaponShoot,
+                GameEvent::AsteroidExplosion
+                | GameEvent::SaucerDestroyed
+                | GameEvent::PlayerDestroyed => Sound::AsteroidExplosion,
+                GameEvent::ExtraLife | GameEvent::SectorCleared => Sound::MenuValidate,
+                GameEvent::PowerUpCollected => Sound::MenuSelection,
+                GameEvent::Hyperspace => Sound::Hyperspace,
+                GameEvent::ShieldImpact => Sound::MenuBack,
+                GameEvent::GameOver => continue,
+            };
+            play(sound);
+        }
+    }
+}
 
-            if let Some(Button::Keyboard(_)) = event.press_args() {
-                if has_released {
-                    break;
-                }
-                has_pressed = true;
+impl Screen for Game {
+    fn enter(&mut self, resources: &mut Resources) {
+        resources.play_music(Music::Action);
+    }
+
+    /// Advances the simulation in fixed steps to catch up with `args.dt` seconds of real time.
+    fn update(&mut self, resources: &mut Resources, args: UpdateArgs) -> Transition {
+        // Cap the time simulated in one go so a long stall (such as dragging the window)
+        // does not make the game spend ever longer trying to catch up.
+        self.accumulator = (self.accumulator + args.dt).min(MAX_FRAME_TIME);
+        while self.accumulator >= TIMESTEP {
+            if self.playback_finished() {
+                return Transition::Pop;
+            }
+            match self.playback {
+                Some(ref mut playback) => self.actions = playback.actions(self.tick),
+                None => self.recording.record(self.tick, self.actions),
             }
+            let events = self.state.step(TIMESTEP, &self.actions);
+            Game::play_sounds(&events, |sound| resources.play_sound(sound));
+            self.accumulator -= TIMESTEP;
+            self.tick += 1;
 
-            if let Some(Button::Keyboard(_)) = event.release_args() {
-                if has_pressed {
-                    has_released = true;
-                }
+            if self.state.game_over() {
+                self.save_recording();
+                return Transition::Replace(Box::new(GameOver::new(
+                    self.state.score(),
+                    self.state.seed(),
+                    self.state.bounds(),
+                )));
             }
         }
+        Transition::None
     }
 
-    /// Draws all current live objects onto the screen as well as the current score.
-    fn draw(&self, context: Context, graphics: &mut GlGraphics, glyph_cache: &mut GlyphCache<'_>) {
-        clear(color::BLACK, graphics);
-        for bullet in &self.bullets {
-            bullet.draw(context, graphics);
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        Game::draw(self, context, graphics, &mut resources.glyph_cache);
+    }
+
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition {
+        let key = match input.button {
+            Button::Keyboard(key) => key,
+            _ => return Transition::None,
+        };
+        let held = input.state == ButtonState::Press;
+
+        if key == Key::X && held {
+            resources.play_sound(Sound::MenuBack);
+            self.save_recording();
+            return Transition::Pop;
         }
-        self.player.draw(context, graphics);
-        for asteroid in &self.asteroids {
-            asteroid.draw(context, graphics);
+
+        // A replay is controlled by its recording rather than the keyboard.
+        if self.playback.is_some() {
+            return Transition::None;
         }
+        resources.controls.apply(key, held, &mut self.actions);
+        Transition::None
+    }
+}
+
+/// Game over screen shown after the player has lost.
+struct GameOver {
+    score: i64,
+    seed: u64,
+    window_size: Size,
 
+    /// Whether a key has been pressed and released since the game ended.
+    has_pressed: bool,
+    has_released: bool,
+}
+
+impl GameOver {
+    fn new(score: i64, seed: u64, window_size: Size) -> Self {
+        GameOver {
+            score,
+            seed,
+            window_size,
+            has_pressed: false,
+            has_released: false,
+        }
+    }
+}
+
+impl Screen for GameOver {
+    fn enter(&mut self, resources: &mut Resources) {
+        resources.play_music(Music::GameOver);
+    }
+
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        let glyph_cache: &mut GlyphCache<'_> = &mut resources.glyph_cache;
+        clear(color::BLACK, graphics);
         text(
-            color::YELLOW,
-            26,
+            color::WHITE,
+            50,
+            "Game Over",
+            glyph_cache,
+            context.transform.trans(
+                self.window_size.width / 2.0 - 120.0,
+                self.window_size.height / 2.0 - 30.0,
+            ),
+            graphics,
+        )
+        .unwrap();
+        let offset = (self.score.to_string().len() * 5) as f64;
+        text(
+            color::WHITE,
+            50,
             format!("Score: {}", self.score).as_str(),
             glyph_cache,
-            context.transform.trans(10.0, 20.0),
+            context.transform.trans(
+                self.window_size.width / 2.0 - 90.0 - offset,
+                self.window_size.height / 2.0 + 30.0,
+            ),
+            graphics,
+        )
+        .unwrap();
+        text(
+            color::WHITE,
+            18,
+            format!("Seed: {}", self.seed).as_str(),
+            glyph_cache,
+            context
+                .transform
+                .trans(10.0, self.window_size.height - 10.0),
             graphics,
         )
         .unwrap();
     }
-}
-
-impl Updateable for Game {
-    fn update(&mut self, args: UpdateArgs) {
-        self.player.update(args);
-        if self.player.should_shoot() {
-            music::play_sound(
-                &Sound::WeaponShoot,
-                music::Repeat::Times(0),
-                self.volume.sound,
-            );
-            self.bullets.push(bullet::Bullet::new(
-                self.player.pos,
-                self.player.vel,
-                self.player.rot,
-                self.window_size,
-            ));
-            self.player.reset_weapon_cooldown();
-        }
 
-        // Update bullet position and remove those that time out.
-        for bullet in &mut self.bullets {
-            bullet.update(args);
-        }
-        self.bullets.retain(|bullet| bullet.ttl() > 0.0);
-
-        for asteroid in &mut self.asteroids {
-            asteroid.update(args);
-        }
-
-        // Shorten lifetimes due to issues trying to pass `self` into a closure.
-        {
-            let bullets = &mut self.bullets;
-            let asteroids = &mut self.asteroids;
-            let player = &mut self.player;
-            let score = &mut self.score;
-            let volume = self.volume;
-
-            bullets.retain(|bullet| {
-                // Remove the first asteroid that collides with a bullet, if any.
-                if let Some(index) = asteroids
-                    .iter()
-                    .position(|asteroid| asteroid.collides_with(bullet))
-                {
-                    if asteroids[index].can_split() {
-                        let new_asteroids = asteroids[index].split(bullet);
-                        asteroids.extend(new_asteroids);
+    fn handle_input(&mut self, _resources: &mut Resources, input: ButtonArgs) -> Transition {
+        // Wait for the player to have pressed and release a key before
+        // continuing in case they were holding a button down during
+        // game over.
+        if let Button::Keyboard(_) = input.button {
+            match input.state {
+                ButtonState::Press => {
+                    if self.has_released {
+                        return Transition::Pop;
+                    }
+                    self.has_pressed = true;
+                }
+                ButtonState::Release => {
+                    if self.has_pressed {
+                        self.has_released = true;
                     }
-                    asteroids.remove(index);
-                    *score += 10;
-                    music::play_sound(
-                        &Sound::AsteroidExplosion,
-                        music::Repeat::Times(0),
-                        volume.sound,
-                    );
-                    return false;
                 }
-                true
-            });
-
-            // If player hits an asteroid, return to the main menu.
-            if asteroids
-                .iter()
-                .any(|asteroid| asteroid.collides_with(player))
-            {
-                self.game_over = true;
             }
         }
+        Transition::None
+    }
+}
 
-        // Countdown a timer which controls when the next asteroid is spawned.
-        self.asteroid_timer -= args.dt;
-        if self.asteroid_timer < 0.0 {
-            self.asteroids
-                .push(asteroid::Asteroid::new(self.window_size));
-
-            // After spawning an asteroid, reduce the timer to spawn the next
-            // so that asteroids gradually being spawning faster and faster, up to a
-            // certain limit.
-            if self.asteroid_timer_max > 0.5 {
-                self.asteroid_timer_max -= 0.075;
-            }
-            self.asteroid_timer = self.asteroid_timer_max;
-        }
+#[cfg(test)]
+mod tests {
+    use super::models::player::Actions;
+    use super::models::testing::BOUNDS;
+    use super::models::weapon::WeaponKind;
+    use super::state::{GameEvent, GameState, Rules, TIMESTEP};
+    use super::Game;
+    use crate::audio::{AudioSink, RecordingAudio, Sound, MAX_VOLUME};
+
+    #[test]
+    fn plays_a_sound_for_each_event_that_has_one() {
+        let mut audio = RecordingAudio::default();
+        let events = [
+            GameEvent::WeaponShoot(WeaponKind::Laser),
+            GameEvent::AsteroidExplosion,
+            GameEvent::GameOver,
+            GameEvent::Hyperspace,
+        ];
+        Game::play_sounds(&events, |sound| audio.play_sound(sound, MAX_VOLUME));
+        let sounds: Vec<_> = audio.sounds.iter().map(|&(sound, _)| sound).collect();
+        assert_eq!(
+            sounds,
+            [Sound::Laser, Sound::AsteroidExplosion, Sound::Hyperspace]
+        );
+    }
+
+    #[test]
+    fn shooting_plays_the_weapon_sound() {
+        let mut audio = RecordingAudio::default();
+        let mut state = GameState::new(BOUNDS, 1, Rules::new());
+        let shooting = Actions {
+            is_shooting: true,
+            ..Actions::default()
+        };
+        let events = state.step(TIMESTEP, &shooting);
+        Game::play_sounds(&events, |sound| audio.play_sound(sound, 0.5));
+        assert_eq!(audio.sounds, [(Sound::WeaponShoot, 0.5)]);
     }
 }
@@ -2,11 +2,11 @@
 use std::f64;
 use std::f64::consts::PI;
 
-use opengl_graphics::GlGraphics;
-use piston_window::{polygon, Context, Size, Transformed, UpdateArgs};
-use rand;
+use piston_window::{polygon, Context, Graphics, Size, Transformed, UpdateArgs};
+use rand::Rng;
 
 use crate::game::color;
+use crate::game::models::geometry;
 use crate::game::models::vector::Vector;
 use crate::game::models::{Collidable, Drawable, Positioned, Updateable, PI_MULT_2};
 
@@ -14,31 +14,49 @@ use crate::game::models::{Collidable, Drawable, Positioned, Updateable, PI_MULT_
 const RADIUS_MIN: f64 = 15.0;
 const RADIUS_MAX: f64 = 70.0;
 
+/// Whole asteroids at least this large count as large rather than medium.
+const LARGE_RADIUS: f64 = (RADIUS_MIN + RADIUS_MAX) / 2.0;
+
 /// Asteroids' shapes are made by mutating a circle, this is a magic number used to tune that.
 const MAX_MUT_FACTOR: f64 = 4.0;
 
 /// Asteroids' shapes are made by mutating a circle, this is a magic number used to tune that.
 const DEFAULT_NUM_VERTS: usize = 20;
 
+/// Seconds a laser beam takes to destroy an asteroid, per pixel of its average radius.
+const BURN_TIME: f64 = 0.01;
+
+/// How large an asteroid is, which decides how many points destroying it is worth.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SizeClass {
+    Large,
+    Medium,
+
+    /// Fragments of a split asteroid.
+    Small,
+}
+
 /// Asteroids are shapes that randomly float around the screen.
 /// They have several properties:
 /// * `pos`: the asteroid's position
-/// * `vel`: the asteroid's velocity
+/// * `vel`: the asteroid's velocity in pixels per second
 /// * `rot`: the asteroid's current rotation
-/// * `spin`: the asteroid's angular velocity
-/// * `radius`: the average radius of the asteroid, used for collision detection
+/// * `spin`: the asteroid's angular velocity in radians per second
+/// * `average_radius`: the average radius of the asteroid, used to decide when it is on-screen
+/// * `bounding_radius`: the distance to the furthest vertex, used for collision detection
 /// * `shape`: an array representing the the drawn shape of the asteroid
-/// * `window_size`: the size of the opengl window, used to wrap position
 /// * `on_screen`: a flag storing whether the asteroid is fully on-screen
+/// * `heat`: the seconds the asteroid has been burnt by a laser beam
 pub struct Asteroid {
     pos: Vector,
     vel: Vector,
     rot: f64,
     spin: f64,
-    radius: f64,
+    average_radius: f64,
+    bounding_radius: f64,
     shape: Vec<[f64; 2]>,
-    window_size: Size,
     on_screen: bool,
+    heat: f64,
 }
 
 /// This function ingests a radius as a float and generates a parametric circle of
@@ -64,11 +82,11 @@ fn generate_circle(radius: f64, num_segments: usize) -> Vec<[f64; 2]> {
 /// * subtract the average position of the vertices from each of them. This
 ///   ensures that the shape is roughly centered around 0. We aren't actually
 ///   doing a real center-of-mass calculation, but this looks pretty good.
-fn randomize_shape(mut shape: Vec<[f64; 2]>, max: f64) -> Vec<[f64; 2]> {
+fn randomize_shape<R: Rng>(mut shape: Vec<[f64; 2]>, max: f64, rng: &mut R) -> Vec<[f64; 2]> {
     let mut average = Vector::default();
     for vertex in &mut shape {
         // Here we create a pair of random values and add them to a vertex.
-        let rand_vect = Vector::new_rand(0.0, 0.0, max, max);
+        let rand_vect = Vector::new_rand(rng, 0.0, 0.0, max, max);
         vertex[0] += rand_vect.x;
         vertex[1] += rand_vect.y;
 
@@ -89,15 +107,15 @@ fn randomize_shape(mut shape: Vec<[f64; 2]>, max: f64) -> Vec<[f64; 2]> {
 /// Given a radius, this function returns a `Vec<[f64; 2]>`
 /// containing a jagged 'randomized' circle. This is then
 /// used as the drawn shape of the asteroid
-fn generate_jagged_shape(radius: f64, num_segments: usize) -> Vec<[f64; 2]> {
+fn generate_jagged_shape<R: Rng>(radius: f64, num_segments: usize, rng: &mut R) -> Vec<[f64; 2]> {
     let new_shape = generate_circle(radius, num_segments);
 
     // Here we are setting a maximum distance to mutate a vertex.
     let max_mut = radius / MAX_MUT_FACTOR;
-    randomize_shape(new_shape, max_mut)
+    randomize_shape(new_shape, max_mut, rng)
 }
 
-fn center_mass(shape: &mut Vec<[f64; 2]>) -> Vector {
+fn center_mass(shape: &mut [[f64; 2]]) -> Vector {
     let mut average = Vector::default();
     for vertex in &mut shape.iter() {
         // Here, we are adding the new vertex location into what will be our average location.
@@ -123,36 +141,52 @@ fn calculate_radius(shape: &[[f64; 2]]) -> f64 {
     avg_magnitude / shape.len() as f64
 }
 
+fn calculate_bounding_radius(shape: &[[f64; 2]]) -> f64 {
+    shape
+        .iter()
+        .map(|vertex| Vector::from(*vertex).magnitude())
+        .fold(0.0, f64::max)
+}
+
 impl Asteroid {
-    pub fn new(window_size: Size) -> Self {
+    pub fn new<R: Rng>(bounds: Size, rng: &mut R) -> Self {
+        Asteroid::scaled(bounds, 1.0, 1.0, rng)
+    }
+
+    /// Creates an asteroid that is `size` times as large and `speed` times as fast as usual.
+    pub fn scaled<R: Rng>(bounds: Size, size: f64, speed: f64, rng: &mut R) -> Self {
+        let (radius_min, radius_max) = (RADIUS_MIN * size, RADIUS_MAX * size);
+
         // First, we generate a random radius, within the specified range, for the new asteroid.
-        let asteroid_radius = RADIUS_MIN + rand::random::<f64>() * (RADIUS_MAX - RADIUS_MIN);
+        let asteroid_radius = radius_min + rng.gen::<f64>() * (radius_max - radius_min);
 
         // Asteroids spawn off-screen at a random point along a circle of a set radius,
         // centered at the middle of the screen. Here we are defining that radius.
-        let spawn_radius = window_size.width.max(window_size.height) + RADIUS_MAX;
+        let spawn_radius = bounds.width.max(bounds.height) + radius_max;
 
         // Here we are generating a random angle, which we will use along with the above radius
         // to calculate the starting point for the new asteroid.
-        let angle = PI_MULT_2 * rand::random::<f64>();
+        let angle = PI_MULT_2 * rng.gen::<f64>();
 
         // The asteroid also has an initial velocity. Right here, we are selecting a random point
-        // on the screen for the asteroid to float towards. The "RADIUS_MAX" sized gaps at the edges
+        // on the screen for the asteroid to float towards. The "radius_max" sized gaps at the edges
         // of the range are there to ensure that every asteroid will, for at least one frame, come
         // fully on-screen, so that the on-screen flag is properly flipped
         let target = Vector::new_rand(
-            RADIUS_MAX,
-            RADIUS_MAX,
-            window_size.width - RADIUS_MAX,
-            window_size.height - RADIUS_MAX,
+            rng,
+            radius_max,
+            radius_max,
+            bounds.width - radius_max,
+            bounds.height - radius_max,
         );
 
-        // Now that the asteroid's direction is decided, we decide its speed.
-        let vel_multiplier = 0.5 + rand::random::<f64>() * 0.7;
+        // Now that the asteroid's direction is decided, we decide its speed in pixels per second.
+        let vel_multiplier = (60.0 + rng.gen::<f64>() * 84.0) * speed;
         let new_pos = Vector {
-            x: window_size.width / 2.0 + spawn_radius * angle.cos(),
-            y: window_size.height / 2.0 + spawn_radius * angle.sin(),
+            x: bounds.width / 2.0 + spawn_radius * angle.cos(),
+            y: bounds.height / 2.0 + spawn_radius * angle.sin(),
         };
+        let shape = generate_jagged_shape(asteroid_radius, DEFAULT_NUM_VERTS, rng);
         Asteroid {
             pos: new_pos,
             vel: Vector {
@@ -161,24 +195,47 @@ impl Asteroid {
             },
             rot: 0.0,
 
-            // Spin rate is random within a fixed range.
-            spin: (rand::random::<f64>() - 0.5) * f64::consts::PI / 180.0,
-            radius: asteroid_radius,
-            shape: generate_jagged_shape(asteroid_radius, DEFAULT_NUM_VERTS),
-            window_size,
+            // Spin rate is random within a fixed range of up to a third of a turn per second.
+            spin: (rng.gen::<f64>() - 0.5) * 2.0 * f64::consts::PI / 3.0,
+            average_radius: asteroid_radius,
+            bounding_radius: calculate_bounding_radius(&shape),
+            shape,
 
             // All asteroids start off-screen.
             on_screen: false,
+            heat: 0.0,
         }
     }
 
+    /// Velocity in pixels per second.
+    pub fn vel(&self) -> Vector {
+        self.vel
+    }
+
     pub fn can_split(&self) -> bool {
         self.shape.len() > 10
     }
 
-    pub fn split<P: Positioned>(&mut self, other: &P) -> Vec<Asteroid> {
+    pub fn size_class(&self) -> SizeClass {
+        if !self.can_split() {
+            SizeClass::Small
+        } else if self.average_radius >= LARGE_RADIUS {
+            SizeClass::Large
+        } else {
+            SizeClass::Medium
+        }
+    }
+
+    /// Burns the asteroid with a laser beam for `dt` seconds. Returns whether it has
+    /// been burnt for long enough to be destroyed.
+    pub fn burn(&mut self, dt: f64) -> bool {
+        self.heat += dt;
+        self.heat >= self.average_radius * BURN_TIME
+    }
+
+    pub fn split<P: Positioned>(&mut self, other: &P, bounds: Size) -> Vec<Asteroid> {
         self.normalize_rotation();
-        let index_nearest = self.index_nearest_point(other);
+        let index_nearest = self.index_nearest_point(other, bounds);
         let num_pieces = 3;
         let mut chunks: Vec<Asteroid> = Vec::new();
         let chunk_size = self.shape.len() / num_pieces;
@@ -204,13 +261,14 @@ impl Asteroid {
             let new_radius = calculate_radius(&new_shape);
             chunks.push(Asteroid {
                 pos: self.pos + average_pos,
-                vel: self.vel + average_pos.rotate(PI / 2.0) * self.spin + average_pos * 0.005,
+                vel: self.vel + average_pos.rotate(PI / 2.0) * self.spin + average_pos * 0.6,
                 rot: 0.0,
                 spin: self.spin * 0.5,
-                radius: new_radius,
+                average_radius: new_radius,
+                bounding_radius: calculate_bounding_radius(&new_shape),
                 shape: new_shape,
-                window_size: self.window_size,
                 on_screen: true,
+                heat: 0.0,
             })
         }
         chunks
@@ -228,8 +286,14 @@ impl Asteroid {
         self.rot = 0.0;
     }
 
-    fn index_nearest_point<P: Positioned>(&mut self, other: &P) -> usize {
-        let other_pos = other.pos();
+    /// The index of the vertex nearest to `other`, or to its nearest copy across the
+    /// edges of the playfield when both wrap around them.
+    fn index_nearest_point<P: Positioned>(&mut self, other: &P, bounds: Size) -> usize {
+        let offset = if self.wraps() && other.wraps() {
+            self.pos.wrapped_delta(other.pos(), bounds.into())
+        } else {
+            other.pos() - self.pos
+        };
         let nearest_point = self
             .shape
             .iter()
@@ -237,7 +301,7 @@ impl Asteroid {
                 x: vert[0],
                 y: vert[1],
             })
-            .map(|vert| other_pos.distance(vert.rotate(self.rot) + self.pos))
+            .map(|vert| offset.distance(vert.rotate(self.rot)))
             .enumerate()
             .min_by_key(|&(_, b)| b as i64);
         nearest_point.unwrap().0
@@ -245,8 +309,7 @@ impl Asteroid {
 }
 
 impl Updateable for Asteroid {
-    #[allow(unused_variables)]
-    fn update(&mut self, args: UpdateArgs) {
+    fn update(&mut self, args: UpdateArgs, bounds: Size) {
         // If the on-screen flag is true, then the update logic
         // works like every other model. If not, then we don't
         // apply the modulus operation, allowing our asteroid to fly
@@ -255,22 +318,22 @@ impl Updateable for Asteroid {
         // have to worry about 'losing' one forever off-screen.
         if self.on_screen {
             // This version of the logic uses modulus.
-            self.pos += self.vel + self.window_size.into();
-            self.pos %= self.window_size.into();
+            self.pos += self.vel * args.dt + bounds.into();
+            self.pos %= bounds.into();
         } else {
             // This is the "floating onto screen" logic which does not use modulus.
-            self.pos += self.vel;
+            self.pos += self.vel * args.dt;
         }
-        self.rot += self.spin;
+        self.rot += self.spin * args.dt;
 
         // This code is useful at the beginning of an asteroid's life.
         // It checks whether the asteroid is fully on-screen. If it is,
         // it sets the on_Screen flag and this code isn't touched again.
         if !self.on_screen
-            && self.pos.x > self.radius
-            && self.pos.x + self.radius < self.window_size.width
-            && self.pos.y > self.radius
-            && self.pos.y + self.radius < self.window_size.height
+            && self.pos.x > self.average_radius
+            && self.pos.x + self.average_radius < bounds.width
+            && self.pos.y > self.average_radius
+            && self.pos.y + self.average_radius < bounds.height
         {
             self.on_screen = true;
         }
@@ -278,13 +341,19 @@ impl Updateable for Asteroid {
 }
 
 impl Drawable for Asteroid {
-    fn draw(&self, context: Context, graphics: &mut GlGraphics) {
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
+        // Glow from white towards orange as a laser beam burns the asteroid.
+        let burnt = (self.heat / (self.average_radius * BURN_TIME)).min(1.0) as f32;
+        let mut asteroid_color = color::WHITE;
+        asteroid_color[1] -= 0.4 * burnt;
+        asteroid_color[2] -= burnt;
+
         // This polygon is the "main" asteroid shape within the frame. It is
         // drawn at the location specified in `pos`. The Vec<[f64; 2]> type,
         // being a list of lists of length 2, is an acceptable "shape" for
         // the polygon function
         polygon(
-            color::WHITE,
+            asteroid_color,
             &self.shape,
             context
                 .transform
@@ -292,58 +361,10 @@ impl Drawable for Asteroid {
                 .rot_rad(self.rot),
             graphics,
         );
+    }
 
-        // Asteroids are large enough that we need to render them on the opposite
-        // side of the canvas whenever they start to go off-screen. However, we
-        // do not want to do this while the asteroid is still entering the screen.
-        // Hence the check for self.on_screen. This code correctly handles wrapped
-        // drawing of asteroid drawing along the edges of the frame, but fails in
-        // the (literal) corner case, as it will never produce a wrapped asteroid
-        // drawing in a corner when an asteroid approaches the opposing corner.
-        if self.on_screen {
-            if self.pos.x + self.radius > self.window_size.width {
-                polygon(
-                    color::WHITE,
-                    &self.shape,
-                    context
-                        .transform
-                        .trans(self.pos.x - self.window_size.width, self.pos.y)
-                        .rot_rad(self.rot),
-                    graphics,
-                )
-            } else if self.pos.x < self.radius {
-                polygon(
-                    color::WHITE,
-                    &self.shape,
-                    context
-                        .transform
-                        .trans(self.pos.x + self.window_size.width, self.pos.y)
-                        .rot_rad(self.rot),
-                    graphics,
-                )
-            }
-            if self.pos.y + self.radius > self.window_size.height {
-                polygon(
-                    color::WHITE,
-                    &self.shape,
-                    context
-                        .transform
-                        .trans(self.pos.x, self.pos.y - self.window_size.height)
-                        .rot_rad(self.rot),
-                    graphics,
-                )
-            } else if self.pos.y < self.radius {
-                polygon(
-                    color::WHITE,
-                    &self.shape,
-                    context
-                        .transform
-                        .trans(self.pos.x, self.pos.y + self.window_size.height)
-                        .rot_rad(self.rot),
-                    graphics,
-                )
-            }
-        }
+    fn draw_radius(&self) -> f64 {
+        self.bounding_radius
     }
 }
 
@@ -351,10 +372,19 @@ impl Positioned for Asteroid {
     fn pos(&self) -> Vector {
         self.pos
     }
+
+    /// Asteroids only wrap once they have fully entered the screen.
+    fn wraps(&self) -> bool {
+        self.on_screen
+    }
 }
 
 impl Collidable for Asteroid {
     fn radius(&self) -> f64 {
-        self.radius
+        self.bounding_radius
+    }
+
+    fn outline(&self) -> Option<Vec<Vector>> {
+        Some(geometry::to_world(&self.shape, self.pos, self.rot))
     }
 }
@@ -0,0 +1,200 @@
+//! Defines the laser beam component.
+//!
+//! A `Beam` is a straight line cast from the ship's nose for as long as the
+//! laser is fired. It stops at the first shape it touches, including shapes
+//! on the other side of the playfield's edges, but does not damage anything
+//! itself; the game decides what happens to the shape it hit.
+
+use piston_window::{line, types, Context, Graphics, Size, Transformed};
+
+use crate::game::color;
+use crate::game::models::geometry;
+use crate::game::models::vector::Vector;
+use crate::game::models::{Collidable, Drawable, Positioned};
+
+/// The glow around the beam, drawn from the outermost layer in, as a color and a
+/// line radius in pixels.
+const GLOW: &[(types::Color, f64)] = &[
+    ([1.0, 0.0, 0.0, 0.15], 4.0),
+    ([1.0, 0.2, 0.2, 0.35], 2.0),
+    (color::WHITE, 0.75),
+];
+
+pub struct Beam {
+    start: Vector,
+
+    /// The direction of the beam in radians.
+    rot: f64,
+    length: f64,
+}
+
+impl Beam {
+    /// Casts a beam from `start` in the direction `rot` that reaches up to `range`
+    /// pixels, clipped at the first of `targets` it touches. Returns the beam along with
+    /// the index given with the target it hit, if any.
+    pub fn cast<'a, C, I>(
+        start: Vector,
+        rot: f64,
+        range: f64,
+        targets: I,
+        bounds: Size,
+    ) -> (Self, Option<usize>)
+    where
+        C: Collidable + 'a,
+        I: IntoIterator<Item = (usize, &'a C)>,
+    {
+        let direction = Vector {
+            x: rot.cos(),
+            y: rot.sin(),
+        };
+        let end = start + direction * range;
+
+        // The beam can cross the edges of the playfield, so it is checked against
+        // each copy of a target around the playfield as well. Targets that are still
+        // entering the playfield can only be hit on the part of them that is inside it.
+        let in_bounds = |point: Vector| {
+            (0.0..bounds.width).contains(&point.x) && (0.0..bounds.height).contains(&point.y)
+        };
+        let mut hit: Option<(f64, usize)> = None;
+        for (index, target) in targets {
+            for row in [-1.0, 0.0, 1.0] {
+                for column in [-1.0, 0.0, 1.0] {
+                    let shift = Vector {
+                        x: column * bounds.width,
+                        y: row * bounds.height,
+                    };
+                    let center = target.pos() + shift;
+                    if geometry::distance_to_segment(center, start, end) > target.radius() {
+                        continue;
+                    }
+                    let distance = match target.outline() {
+                        Some(outline) => {
+                            let shifted: Vec<_> =
+                                outline.into_iter().map(|vertex| vertex + shift).collect();
+                            geometry::ray_hits_polygon(start, direction, &shifted)
+                        }
+                        None => {
+                            geometry::ray_hits_circle(start, direction, center, target.radius())
+                        }
+                    };
+                    let visible = |&distance: &f64| {
+                        target.wraps() || in_bounds(start + direction * distance - shift)
+                    };
+                    let distance = distance
+                        .filter(|&distance| distance <= range)
+                        .filter(visible);
+                    if let Some(distance) = distance {
+                        if hit.is_none_or(|(nearest, _)| distance < nearest) {
+                            hit = Some((distance, index));
+                        }
+                    }
+                }
+            }
+        }
+
+        let beam = Beam {
+            start,
+            rot,
+            length: hit.map_or(range, |(distance, _)| distance),
+        };
+        (beam, hit.map(|(_, index)| index))
+    }
+
+    /// The point where the beam stops.
+    pub fn end(&self) -> Vector {
+        self.start
+            + Vector {
+                x: self.rot.cos(),
+                y: self.rot.sin(),
+            } * self.length
+    }
+}
+
+impl Drawable for Beam {
+    /// Draws the beam centered on its midpoint, as a white core inside a red glow.
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
+        let half = self.length / 2.0;
+        let transform = context
+            .transform
+            .trans(self.pos().x, self.pos().y)
+            .rot_rad(self.rot);
+        for &(glow_color, radius) in GLOW {
+            line(
+                glow_color,
+                radius,
+                [-half, 0.0, half, 0.0],
+                transform,
+                graphics,
+            );
+        }
+    }
+
+    fn draw_radius(&self) -> f64 {
+        self.length / 2.0
+    }
+}
+
+/// A beam is positioned at its midpoint, so that it is drawn across the edges of
+/// the playfield like any other model.
+impl Positioned for Beam {
+    fn pos(&self) -> Vector {
+        (self.start + self.end()) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Beam;
+    use crate::game::models::testing::{Shape, BOUNDS};
+    use crate::game::models::vector::Vector;
+
+    #[test]
+    fn stops_at_the_nearest_target() {
+        let start = Vector { x: 100.0, y: 300.0 };
+        let (far, near) = (Shape::square(300.0, 300.0), Shape::square(200.0, 300.0));
+        let (beam, hit) = Beam::cast(start, 0.0, 400.0, [(0, &far), (1, &near)], BOUNDS);
+        assert_eq!(hit, Some(1));
+        assert_eq!(beam.end().x, 195.0);
+    }
+
+    #[test]
+    fn reaches_its_full_range_without_a_target() {
+        let start = Vector { x: 100.0, y: 300.0 };
+        let beside = Shape::square(200.0, 320.0);
+        let (beam, hit) = Beam::cast(start, 0.0, 400.0, [(0, &beside)], BOUNDS);
+        assert_eq!(hit, None);
+        assert_eq!(beam.end().x, 500.0);
+    }
+
+    #[test]
+    fn hits_targets_across_edges() {
+        let start = Vector { x: 790.0, y: 300.0 };
+        let across = Shape::square(20.0, 300.0);
+        let (beam, hit) = Beam::cast(start, 0.0, 400.0, [(0, &across)], BOUNDS);
+        assert_eq!(hit, Some(0));
+        assert_eq!(beam.end().x, 815.0);
+
+        // Firing away from the edge misses it.
+        let (_, hit) = Beam::cast(start, std::f64::consts::PI, 400.0, [(0, &across)], BOUNDS);
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn only_hits_entering_targets_inside_the_playfield() {
+        // Just past the edge, so not on the screen yet.
+        let mut outside = Shape::square(815.0, 300.0);
+        outside.wraps = false;
+        let start = Vector { x: 790.0, y: 300.0 };
+        let (beam, hit) = Beam::cast(start, 0.0, 400.0, [(0, &outside)], BOUNDS);
+        assert_eq!(hit, None);
+        assert_eq!(beam.end().x, 1190.0);
+
+        // On the screen, reached by the beam across the opposite edge.
+        let mut entering = Shape::square(785.0, 300.0);
+        entering.wraps = false;
+        let start = Vector { x: 10.0, y: 300.0 };
+        let (beam, hit) = Beam::cast(start, std::f64::consts::PI, 400.0, [(0, &entering)], BOUNDS);
+        assert_eq!(hit, Some(0));
+        assert_eq!(beam.end().x, -10.0);
+    }
+}
@@ -3,61 +3,91 @@
 //! `Bullet`s are created with a specific position and velocity
 //! which they use to move across the screen. They are not
 //! responsible for removing themselves or handling collisions with
-//! other models.
+//! other models. How fast, how far and how large a bullet is is described
+//! by its `Projectile`.
 
-use opengl_graphics::GlGraphics;
-use piston_window::{ellipse, types, Context, Size, Transformed, UpdateArgs};
+use piston_window::{ellipse, Context, Graphics, Size, Transformed, UpdateArgs};
 
 use crate::game::color;
 use crate::game::models::vector::Vector;
 use crate::game::models::{Collidable, Drawable, Positioned, Updateable};
 
+/// The kind of bullet a weapon fires.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Projectile {
+    /// Speed relative to the shooter in pixels per second.
+    pub speed: f64,
+
+    /// Seconds before the bullet disappears.
+    pub ttl: f64,
+    pub diameter: f64,
+
+    /// Whether the bullet keeps going after destroying an asteroid.
+    pub piercing: bool,
+}
+
+impl Projectile {
+    /// The bullet fired by the ship's blaster and by saucers.
+    pub const STANDARD: Projectile = Projectile {
+        speed: 480.0,
+        ttl: 1.0,
+        diameter: 3.0,
+        piercing: false,
+    };
+}
+
 pub struct Bullet {
     pos: Vector,
     vel: Vector,
     ttl: f64,
-    window_size: Size,
+    diameter: f64,
+    piercing: bool,
 }
 
 impl Bullet {
-    pub fn new(position: Vector, velocity: Vector, direction: f64, window_size: Size) -> Self {
-        let speed_multiplier = 4.0;
+    pub fn new(position: Vector, velocity: Vector, direction: f64, projectile: Projectile) -> Self {
         Bullet {
             pos: position,
             vel: Vector {
-                x: speed_multiplier * direction.cos() + velocity.x,
-                y: speed_multiplier * direction.sin() + velocity.y,
+                x: projectile.speed * direction.cos() + velocity.x,
+                y: projectile.speed * direction.sin() + velocity.y,
             },
-            ttl: 1.0,
-            window_size,
+            ttl: projectile.ttl,
+            diameter: projectile.diameter,
+            piercing: projectile.piercing,
         }
     }
 
     pub fn ttl(&self) -> f64 {
         self.ttl
     }
+
+    pub fn is_piercing(&self) -> bool {
+        self.piercing
+    }
 }
 
 impl Updateable for Bullet {
-    fn update(&mut self, args: UpdateArgs) {
-        self.pos += self.vel + self.window_size.into();
-        self.pos %= self.window_size.into();
+    fn update(&mut self, args: UpdateArgs, bounds: Size) {
+        self.pos += self.vel * args.dt + bounds.into();
+        self.pos %= bounds.into();
         self.ttl -= args.dt;
     }
 }
 
-const BULLET_DIAMETER: f64 = 3.0;
 impl Drawable for Bullet {
-    fn draw(&self, context: Context, graphics: &mut GlGraphics) {
-        const BULLET: types::Rectangle = [0.0, 0.0, BULLET_DIAMETER, BULLET_DIAMETER];
-
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
         ellipse(
             color::WHITE,
-            BULLET,
+            ellipse::circle(0.0, 0.0, self.diameter / 2.0),
             context.transform.trans(self.pos.x, self.pos.y),
             graphics,
         )
     }
+
+    fn draw_radius(&self) -> f64 {
+        self.diameter / 2.0
+    }
 }
 
 impl Positioned for Bullet {
@@ -68,6 +98,6 @@ impl Positioned for Bullet {
 
 impl Collidable for Bullet {
     fn radius(&self) -> f64 {
-        BULLET_DIAMETER / 2.0
+        self.diameter / 2.0
     }
 }
@@ -0,0 +1,315 @@
+//! Polygon intersection tests used to check collisions against the exact shapes of models.
+//!
+//! Polygons are lists of vertices in world coordinates. They may be concave, such as
+//! the jagged outline of an asteroid, but must not intersect themselves.
+
+use crate::game::models::vector::Vector;
+
+/// Moves the vertices of a shape, given relative to its own origin, into the world by
+/// rotating them `rot` radians and then translating them to `pos`.
+pub fn to_world(shape: &[[f64; 2]], pos: Vector, rot: f64) -> Vec<Vector> {
+    let (sin, cos) = rot.sin_cos();
+    shape
+        .iter()
+        .map(|vertex| Vector {
+            x: pos.x + vertex[0] * cos - vertex[1] * sin,
+            y: pos.y + vertex[0] * sin + vertex[1] * cos,
+        })
+        .collect()
+}
+
+/// Iterates over the edges of a polygon, including the one closing it.
+fn edges(polygon: &[Vector]) -> impl Iterator<Item = (Vector, Vector)> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&start, &end)| (start, end))
+}
+
+/// The z component of the cross product of `a - origin` and `b - origin`, which is
+/// positive when `b` lies counter-clockwise of `a` as seen from `origin`.
+fn cross(origin: Vector, a: Vector, b: Vector) -> f64 {
+    (a.x - origin.x) * (b.y - origin.y) - (a.y - origin.y) * (b.x - origin.x)
+}
+
+/// Whether the segment `a1 a2` crosses or touches the segment `b1 b2`.
+fn segments_intersect(a1: Vector, a2: Vector, b1: Vector, b2: Vector) -> bool {
+    let d1 = cross(b1, b2, a1);
+    let d2 = cross(b1, b2, a2);
+    let d3 = cross(a1, a2, b1);
+    let d4 = cross(a1, a2, b2);
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+
+    // The segments can also touch where an end point lies on the other segment.
+    let on_segment = |start: Vector, end: Vector, point: Vector| {
+        point.x >= start.x.min(end.x)
+            && point.x <= start.x.max(end.x)
+            && point.y >= start.y.min(end.y)
+            && point.y <= start.y.max(end.y)
+    };
+    (d1 == 0.0 && on_segment(b1, b2, a1))
+        || (d2 == 0.0 && on_segment(b1, b2, a2))
+        || (d3 == 0.0 && on_segment(a1, a2, b1))
+        || (d4 == 0.0 && on_segment(a1, a2, b2))
+}
+
+/// The shortest distance from `point` to the segment `start end`.
+pub fn distance_to_segment(point: Vector, start: Vector, end: Vector) -> f64 {
+    let edge = end - start;
+    let length_squared = edge.x.powi(2) + edge.y.powi(2);
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+    let along = ((point.x - start.x) * edge.x + (point.y - start.y) * edge.y) / length_squared;
+    point.distance(start + edge * along.clamp(0.0, 1.0))
+}
+
+/// The z component of the cross product of `a` and `b`.
+fn perp_dot(a: Vector, b: Vector) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+/// How far along the ray from `origin` in the unit `direction` it crosses the
+/// segment `start end`, if it does at all.
+fn ray_hits_segment(origin: Vector, direction: Vector, start: Vector, end: Vector) -> Option<f64> {
+    let edge = end - start;
+    let denominator = perp_dot(direction, edge);
+    if denominator == 0.0 {
+        return None;
+    }
+    let offset = start - origin;
+    let along_ray = perp_dot(offset, edge) / denominator;
+    let along_edge = perp_dot(offset, direction) / denominator;
+    (along_ray >= 0.0 && (0.0..=1.0).contains(&along_edge)).then_some(along_ray)
+}
+
+/// How far along the ray from `origin` in the unit `direction` it first touches
+/// `polygon`, or 0 if it starts inside it.
+pub fn ray_hits_polygon(origin: Vector, direction: Vector, polygon: &[Vector]) -> Option<f64> {
+    if contains_point(polygon, origin) {
+        return Some(0.0);
+    }
+    edges(polygon)
+        .filter_map(|(start, end)| ray_hits_segment(origin, direction, start, end))
+        .min_by(f64::total_cmp)
+}
+
+/// How far along the ray from `origin` in the unit `direction` it first touches the
+/// circle at `center` with the given `radius`, or 0 if it starts inside it.
+pub fn ray_hits_circle(
+    origin: Vector,
+    direction: Vector,
+    center: Vector,
+    radius: f64,
+) -> Option<f64> {
+    let offset = center - origin;
+    let closest = offset.dot(direction);
+    let miss_squared = offset.dot(offset) - closest.powi(2);
+    if miss_squared > radius.powi(2) {
+        return None;
+    }
+    let half_chord = (radius.powi(2) - miss_squared).sqrt();
+    if closest + half_chord < 0.0 {
+        None
+    } else {
+        Some((closest - half_chord).max(0.0))
+    }
+}
+
+/// Whether `point` lies inside `polygon`, found by counting how many edges a ray
+/// cast from the point crosses. Works for concave polygons.
+pub fn contains_point(polygon: &[Vector], point: Vector) -> bool {
+    let mut inside = false;
+    for (start, end) in edges(polygon) {
+        if (start.y > point.y) != (end.y > point.y) {
+            let crossing_x = start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x);
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Whether two polygons overlap. Either their edges cross, or one lies entirely within the other.
+pub fn polygons_intersect(a: &[Vector], b: &[Vector]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    edges(a).any(|(a1, a2)| edges(b).any(|(b1, b2)| segments_intersect(a1, a2, b1, b2)))
+        || contains_point(b, a[0])
+        || contains_point(a, b[0])
+}
+
+/// Whether a polygon overlaps the circle at `center` with the given `radius`.
+pub fn polygon_intersects_circle(polygon: &[Vector], center: Vector, radius: f64) -> bool {
+    contains_point(polygon, center)
+        || edges(polygon).any(|(start, end)| distance_to_segment(center, start, end) < radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        contains_point, polygon_intersects_circle, polygons_intersect, ray_hits_circle,
+        ray_hits_polygon, segments_intersect, to_world,
+    };
+    use crate::game::models::vector::Vector;
+
+    fn polygon(vertices: &[[f64; 2]]) -> Vec<Vector> {
+        to_world(vertices, Vector { x: 0.0, y: 0.0 }, 0.0)
+    }
+
+    fn square(left: f64, top: f64, size: f64) -> Vec<Vector> {
+        polygon(&[
+            [left, top],
+            [left + size, top],
+            [left + size, top + size],
+            [left, top + size],
+        ])
+    }
+
+    /// A U shape with a dent between x = 10 and x = 20 that is open at the bottom.
+    fn u_shape() -> Vec<Vector> {
+        polygon(&[
+            [0.0, 0.0],
+            [30.0, 0.0],
+            [30.0, 30.0],
+            [20.0, 30.0],
+            [20.0, 10.0],
+            [10.0, 10.0],
+            [10.0, 30.0],
+            [0.0, 30.0],
+        ])
+    }
+
+    #[test]
+    fn point_in_a_concave_dent_is_outside() {
+        let shape = u_shape();
+        assert!(!contains_point(&shape, Vector { x: 15.0, y: 20.0 }));
+        assert!(contains_point(&shape, Vector { x: 5.0, y: 20.0 }));
+        assert!(contains_point(&shape, Vector { x: 15.0, y: 5.0 }));
+        assert!(!contains_point(&shape, Vector { x: 40.0, y: 5.0 }));
+    }
+
+    #[test]
+    fn polygon_in_a_concave_dent_does_not_intersect() {
+        assert!(!polygons_intersect(&u_shape(), &square(12.0, 15.0, 6.0)));
+        assert!(polygons_intersect(&u_shape(), &square(8.0, 15.0, 6.0)));
+    }
+
+    #[test]
+    fn touching_segments_intersect() {
+        let point = |x, y| Vector { x, y };
+        // Crossing, touching at an end point and lying apart.
+        assert!(segments_intersect(
+            point(0.0, 0.0),
+            point(10.0, 10.0),
+            point(0.0, 10.0),
+            point(10.0, 0.0)
+        ));
+        assert!(segments_intersect(
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(5.0, 0.0),
+            point(5.0, 10.0)
+        ));
+        assert!(!segments_intersect(
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(0.0, 1.0),
+            point(10.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn polygons_sharing_an_edge_intersect() {
+        assert!(polygons_intersect(
+            &square(0.0, 0.0, 10.0),
+            &square(10.0, 0.0, 10.0)
+        ));
+        assert!(!polygons_intersect(
+            &square(0.0, 0.0, 10.0),
+            &square(11.0, 0.0, 10.0)
+        ));
+    }
+
+    #[test]
+    fn polygon_inside_another_intersects() {
+        let outer = square(0.0, 0.0, 100.0);
+        let inner = square(40.0, 40.0, 20.0);
+        assert!(polygons_intersect(&outer, &inner));
+        assert!(polygons_intersect(&inner, &outer));
+    }
+
+    #[test]
+    fn circle_grazing_an_edge_intersects() {
+        let shape = square(0.0, 0.0, 10.0);
+        let center = Vector { x: 15.0, y: 5.0 };
+        assert!(polygon_intersects_circle(&shape, center, 5.5));
+        assert!(!polygon_intersects_circle(&shape, center, 4.5));
+        assert!(polygon_intersects_circle(
+            &shape,
+            Vector { x: 5.0, y: 5.0 },
+            1.0
+        ));
+    }
+
+    const RIGHT: Vector = Vector { x: 1.0, y: 0.0 };
+
+    #[test]
+    fn ray_starting_inside_a_polygon_hits_at_once() {
+        let start = Vector { x: 5.0, y: 5.0 };
+        assert_eq!(
+            ray_hits_polygon(start, RIGHT, &square(0.0, 0.0, 10.0)),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn ray_ignores_parallel_edges() {
+        // The top and bottom edges run along the ray, so only the left edge is hit.
+        let start = Vector { x: -10.0, y: 5.0 };
+        assert_eq!(
+            ray_hits_polygon(start, RIGHT, &square(0.0, 0.0, 10.0)),
+            Some(10.0)
+        );
+
+        // Running alongside the polygon without touching it.
+        let beside = Vector { x: -10.0, y: -5.0 };
+        assert_eq!(
+            ray_hits_polygon(beside, RIGHT, &square(0.0, 0.0, 10.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_hits_the_nearest_edge() {
+        // The ray enters the U shape, leaves it through the dent, then enters it again.
+        let start = Vector { x: -10.0, y: 20.0 };
+        assert_eq!(ray_hits_polygon(start, RIGHT, &u_shape()), Some(10.0));
+    }
+
+    #[test]
+    fn ray_does_not_hit_behind_its_start() {
+        let start = Vector { x: 20.0, y: 5.0 };
+        assert_eq!(
+            ray_hits_polygon(start, RIGHT, &square(0.0, 0.0, 10.0)),
+            None
+        );
+        assert_eq!(
+            ray_hits_circle(start, RIGHT, Vector { x: 5.0, y: 5.0 }, 5.0),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_hits_a_circle() {
+        let center = Vector { x: 20.0, y: 3.0 };
+        let start = Vector { x: 0.0, y: 0.0 };
+        assert_eq!(ray_hits_circle(start, RIGHT, center, 5.0), Some(16.0));
+        assert_eq!(ray_hits_circle(start, RIGHT, center, 2.0), None);
+        assert_eq!(ray_hits_circle(center, RIGHT, center, 2.0), Some(0.0));
+    }
+}
@@ -3,26 +3,69 @@
 use std::f64;
 use std::f64::consts::PI;
 
-use opengl_graphics::GlGraphics;
-use piston_window::{Context, UpdateArgs};
+use piston_window::{Context, Graphics, Size, Transformed, UpdateArgs};
 
 pub mod asteroid;
+pub mod beam;
 pub mod bullet;
+mod geometry;
 pub mod player;
-mod vector;
+pub mod popup;
+pub mod powerup;
+pub mod saucer;
+#[cfg(test)]
+pub mod testing;
+pub mod vector;
+pub mod weapon;
 
 const PI_MULT_2: f64 = 2.0 * PI;
 
-/// Trait implemented by types that can be drawn to a window.
+/// Trait implemented by types that can be drawn.
+///
+/// Drawing works with any Piston `Graphics` back-end, not just the OpenGL
+/// window, so models can also be drawn offscreen or into a recording.
 pub trait Drawable {
-    /// Draws oneself to the screen.
-    fn draw(&self, context: Context, graphics: &mut GlGraphics);
+    /// Draws oneself to the given back-end.
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G);
+
+    /// The distance from the position to the furthest point that is drawn.
+    fn draw_radius(&self) -> f64;
+
+    /// Draws oneself on a playfield that wraps around at `bounds`. Wherever oneself
+    /// crosses an edge, a copy is also drawn on the opposite side, for a total of up
+    /// to four copies when crossing two edges near a corner.
+    fn draw_wrapped<G: Graphics>(&self, context: Context, graphics: &mut G, bounds: Size)
+    where
+        Self: Positioned,
+    {
+        if !self.wraps() {
+            self.draw(context, graphics);
+            return;
+        }
+
+        let (pos, radius) = (self.pos(), self.draw_radius());
+        let wrap_offsets = |position: f64, length: f64| {
+            if position + radius > length {
+                [Some(0.0), Some(-length)]
+            } else if position - radius < 0.0 {
+                [Some(0.0), Some(length)]
+            } else {
+                [Some(0.0), None]
+            }
+        };
+        for y_offset in wrap_offsets(pos.y, bounds.height).into_iter().flatten() {
+            for x_offset in wrap_offsets(pos.x, bounds.width).into_iter().flatten() {
+                self.draw(context.trans(x_offset, y_offset), graphics);
+            }
+        }
+    }
 }
 
 /// Trait implemented by types that are updated by a game loop.
 pub trait Updateable {
-    /// Update the state of a type within a game loop.
-    fn update(&mut self, args: UpdateArgs);
+    /// Update the state of a type within a game loop, on a playfield that wraps
+    /// around at `bounds`.
+    fn update(&mut self, args: UpdateArgs, bounds: Size);
 }
 
 /// Defines how a type can expose its position.
@@ -36,16 +79,131 @@ pub trait Positioned {
     }
 
     fn pos(&self) -> vector::Vector;
+
+    /// Whether the type wraps around the edges of the playfield. Types that are
+    /// still entering the playfield from outside of it do not.
+    fn wraps(&self) -> bool {
+        true
+    }
 }
 
 /// Defines how types can expose how they can check for collisions with each other.
 pub trait Collidable: Positioned {
+    /// The radius of a circle around `pos` that fully contains the type's shape.
     fn radius(&self) -> f64;
 
-    /// Check another `Collidable` type to see if it's radius overlaps with this instance's.
-    fn collides_with<C: Collidable>(&self, other: &C) -> bool {
-        // The Distance Formula.
-        let distance = ((self.x() - other.x()).powi(2) + (self.y() - other.y()).powi(2)).sqrt();
-        distance < self.radius() + other.radius()
+    /// The exact shape of the type as a polygon in world coordinates.
+    /// Types without an outline are treated as a circle of `radius`.
+    fn outline(&self) -> Option<Vec<vector::Vector>> {
+        None
+    }
+
+    /// Check another `Collidable` type to see if it's shape overlaps with this instance's.
+    ///
+    /// The playfield wraps around at `bounds`, so two types can also collide across
+    /// its edges. Each type is compared with the nearest copy of the other.
+    fn collides_with<C: Collidable>(&self, other: &C, bounds: Size) -> bool {
+        let delta = if self.wraps() && other.wraps() {
+            self.pos().wrapped_delta(other.pos(), bounds.into())
+        } else {
+            other.pos() - self.pos()
+        };
+
+        // Shapes whose bounding circles do not overlap can never collide,
+        // which rules out most pairs before comparing polygons.
+        if delta.magnitude() >= self.radius() + other.radius() {
+            return false;
+        }
+
+        // Move the other type next to this one, where it is closest.
+        let other_pos = self.pos() + delta;
+        let shift = other_pos - other.pos();
+        let other_outline = other.outline().map(|outline| {
+            outline
+                .into_iter()
+                .map(|vertex| vertex + shift)
+                .collect::<Vec<_>>()
+        });
+
+        match (self.outline(), other_outline) {
+            (Some(outline), Some(other_outline)) => {
+                geometry::polygons_intersect(&outline, &other_outline)
+            }
+            (Some(outline), None) => {
+                geometry::polygon_intersects_circle(&outline, other_pos, other.radius())
+            }
+            (None, Some(other_outline)) => {
+                geometry::polygon_intersects_circle(&other_outline, self.pos(), self.radius())
+            }
+            (None, None) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::player::Player;
+    use super::testing::{Shape, BOUNDS};
+    use super::vector::Vector;
+    use super::Collidable;
+
+    #[test]
+    fn collides_across_left_and_right_edges() {
+        let left = Shape::circle(5.0, 300.0);
+        let right = Shape::circle(795.0, 300.0);
+        assert!(left.collides_with(&right, BOUNDS));
+        assert!(right.collides_with(&left, BOUNDS));
+    }
+
+    #[test]
+    fn collides_across_top_and_bottom_edges() {
+        let top = Shape::circle(400.0, 3.0);
+        let bottom = Shape::circle(400.0, 590.0);
+        assert!(top.collides_with(&bottom, BOUNDS));
+        assert!(bottom.collides_with(&top, BOUNDS));
+    }
+
+    #[test]
+    fn collides_across_corners() {
+        let top_left = Shape::circle(4.0, 4.0);
+        let bottom_right = Shape::circle(796.0, 596.0);
+        let top_right = Shape::circle(796.0, 4.0);
+        let bottom_left = Shape::circle(4.0, 596.0);
+        assert!(top_left.collides_with(&bottom_right, BOUNDS));
+        assert!(top_right.collides_with(&bottom_left, BOUNDS));
+        assert!(top_left.collides_with(&top_right, BOUNDS));
+    }
+
+    #[test]
+    fn does_not_collide_when_apart_on_the_torus() {
+        let left = Shape::circle(15.0, 300.0);
+        let right = Shape::circle(780.0, 300.0);
+        assert!(!left.collides_with(&right, BOUNDS));
+        assert!(!Shape::circle(400.0, 300.0).collides_with(&Shape::circle(0.0, 0.0), BOUNDS));
+    }
+
+    #[test]
+    fn does_not_collide_across_edges_without_wrapping() {
+        let left = Shape::circle(5.0, 300.0);
+        let mut entering = Shape::circle(805.0, 300.0);
+        entering.wraps = false;
+        assert!(!left.collides_with(&entering, BOUNDS));
+        assert!(!entering.collides_with(&left, BOUNDS));
+    }
+
+    #[test]
+    fn collides_with_outline_across_edges() {
+        // The ship points right, so its nose pokes across the right edge.
+        let mut player = Player::new(BOUNDS);
+        player.pos = Vector { x: 792.0, y: 300.0 };
+        player.rot = 0.0;
+        let mut bullet = Shape::circle(2.0, 300.0);
+        bullet.radius = 1.5;
+        assert!(player.collides_with(&bullet, BOUNDS));
+        assert!(bullet.collides_with(&player, BOUNDS));
+
+        // Next to the nose, but outside of the triangle.
+        bullet.pos.y = 306.0;
+        assert!(!player.collides_with(&bullet, BOUNDS));
     }
 }
@@ -7,30 +7,67 @@
 
 use std::f64;
 
-use opengl_graphics::GlGraphics;
-use piston_window::{polygon, types, Context, Size, Transformed, UpdateArgs};
+use piston_window::{
+    ellipse, polygon, types, Context, Ellipse, Graphics, Size, Transformed, UpdateArgs,
+};
 
 use crate::game::color;
+use crate::game::models::bullet::{Bullet, Projectile};
+use crate::game::models::geometry;
+use crate::game::models::powerup::{Effects, Kind};
 use crate::game::models::vector::Vector;
+use crate::game::models::weapon::{self, Weapon};
 use crate::game::models::{Collidable, Drawable, Positioned, Updateable, PI_MULT_2};
 
 pub struct Player {
     pub pos: Vector,
+
+    /// Velocity in pixels per second.
     pub vel: Vector,
     pub rot: f64,
     pub actions: Actions,
+
+    /// The weapons in the ship's slots and the index of the one in use.
+    weapons: Vec<Box<dyn Weapon>>,
+    weapon: usize,
     weapon_cooldown: f64,
-    window_size: Size,
+
+    /// Seconds the weapon in use has been charged for.
+    charge: f64,
+
+    /// Whether switching weapons was held during the previous update, so that
+    /// holding it down switches only once.
+    switching: bool,
+
+    /// Seconds left during which the player cannot be hit.
+    invulnerability: f64,
+    effects: Effects,
+
+    /// Energy left in the shield, from 0 to `MAX_ENERGY`.
+    energy: f64,
+
+    /// Seconds left since the shield absorbed an impact, during which the ship
+    /// bounces away and cannot be hit by another asteroid.
+    impact: f64,
+
+    /// Seconds left until the hyperspace drive jumps. Present only while it is charging.
+    hyperspace_charge: Option<f64>,
+    hyperspace_cooldown: f64,
+
+    /// Seconds left to show the ship arriving from hyperspace.
+    hyperspace_arrival: f64,
 }
 
 /// Currently active user actions.
-#[derive(Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Actions {
     pub rotate_cw: bool,
     pub rotate_ccw: bool,
     pub fire_boosters: bool,
     pub fire_rev_boosters: bool,
     pub is_shooting: bool,
+    pub hyperspace: bool,
+    pub switch_weapon: bool,
 }
 
 #[derive(Copy, Clone)]
@@ -39,21 +76,68 @@ enum Direction {
     Backward,
 }
 
+/// Rotation speed in radians per second.
 const ROTATION_INCREMENT: f64 = 5.0;
-const THRUST_INCREMENT: f64 = 5.0;
+
+/// Acceleration from the boosters in pixels per second squared.
+const THRUST_INCREMENT: f64 = 600.0;
+
+/// How much the rapid fire power-up shortens the time between shots.
+const RAPID_FIRE_FACTOR: f64 = 0.4;
+
+/// The angle in radians between the shots fired with the spread shot power-up.
+const SPREAD_ANGLE: f64 = 0.2;
+
+/// The energy the shield holds when full.
+pub const MAX_ENERGY: f64 = 100.0;
+
+/// Energy the shield regains per second.
+const ENERGY_RECHARGE: f64 = 4.0;
+
+/// Energy used per pixel of asteroid radius and per pixel per second of impact speed.
+const IMPACT_COST: f64 = 0.005;
+
+/// Impacts are treated as at least this fast, so even grazing an asteroid costs energy.
+const MIN_IMPACT_SPEED: f64 = 60.0;
+
+/// The least speed, in pixels per second, the ship bounces away from an asteroid with.
+const MIN_BOUNCE_SPEED: f64 = 80.0;
+
+/// Seconds after an impact during which the ship cannot be hit by another asteroid.
+const IMPACT_TIME: f64 = 0.3;
+
+/// Seconds the hyperspace drive charges for before jumping.
+const HYPERSPACE_CHARGE: f64 = 0.5;
+
+/// Seconds after a jump before the hyperspace drive can be used again.
+const HYPERSPACE_COOLDOWN: f64 = 3.0;
+
+/// Seconds the ring showing the ship's arrival from hyperspace is drawn for.
+const HYPERSPACE_ARRIVAL: f64 = 0.3;
 
 impl Player {
-    pub fn new(window_size: Size) -> Self {
+    /// Creates a player at rest in the center of the playfield.
+    pub fn new(bounds: Size) -> Self {
         Player {
             pos: Vector {
-                x: window_size.width / 2.0,
-                y: window_size.height / 2.0,
+                x: bounds.width / 2.0,
+                y: bounds.height / 2.0,
             },
             vel: Vector { x: 0.0, y: 0.0 },
             rot: 0.0,
             actions: Actions::default(),
+            weapons: weapon::arsenal(),
+            weapon: 0,
             weapon_cooldown: 0.0,
-            window_size,
+            charge: 0.0,
+            switching: false,
+            invulnerability: 0.0,
+            effects: Effects::default(),
+            energy: MAX_ENERGY,
+            impact: 0.0,
+            hyperspace_charge: None,
+            hyperspace_cooldown: 0.0,
+            hyperspace_arrival: 0.0,
         }
     }
 
@@ -66,7 +150,7 @@ impl Player {
     }
 
     fn rotate_ccw(&mut self, delta: f64) {
-        self.rotate(-1.0 * ROTATION_INCREMENT * delta)
+        self.rotate(-ROTATION_INCREMENT * delta)
     }
 
     fn accelerate(&mut self, delta: f64, direction: Direction) {
@@ -89,19 +173,181 @@ impl Player {
         self.accelerate(delta, Direction::Backward);
     }
 
-    pub fn reset_weapon_cooldown(&mut self) {
-        self.weapon_cooldown = 0.25;
+    /// The weapon in use.
+    pub fn weapon(&self) -> &dyn Weapon {
+        self.weapons[self.weapon].as_ref()
+    }
+
+    /// How much the weapon in use has been charged, from 0 to 1.
+    pub fn charge(&self) -> f64 {
+        let max_charge = self.weapon().max_charge();
+        if max_charge > 0.0 {
+            self.charge / max_charge
+        } else {
+            0.0
+        }
+    }
+
+    /// Fires the weapon in use if it is ready and its trigger is held, or for a
+    /// charging weapon, once its trigger is released. Returns the bullets fired.
+    pub fn shoot(&mut self) -> Vec<Bullet> {
+        let weapon = self.weapons[self.weapon].as_ref();
+        let charging = weapon.max_charge() > 0.0;
+        let fires = if weapon.beam_range().is_some() {
+            false
+        } else if charging {
+            !self.actions.is_shooting && self.charge > 0.0
+        } else {
+            self.actions.is_shooting && self.weapon_cooldown == 0.0
+        };
+        if !fires {
+            return Vec::new();
+        }
+
+        let shots = weapon.fire(self.charge());
+        self.charge = 0.0;
+        self.weapon_cooldown = weapon.cooldown();
+        if self.effects.is_active(Kind::RapidFire) {
+            self.weapon_cooldown *= RAPID_FIRE_FACTOR;
+        }
+
+        let (pos, vel, rot) = (self.pos, self.vel, self.rot);
+        let piercing = self.effects.is_active(Kind::Piercing);
+        let angles: &[f64] = if self.effects.is_active(Kind::SpreadShot) {
+            &[-SPREAD_ANGLE, 0.0, SPREAD_ANGLE]
+        } else {
+            &[0.0]
+        };
+        angles
+            .iter()
+            .flat_map(|angle| {
+                shots.iter().map(move |shot| {
+                    Bullet::new(
+                        pos,
+                        vel,
+                        rot + angle + shot.angle,
+                        Projectile {
+                            piercing: shot.projectile.piercing || piercing,
+                            ..shot.projectile
+                        },
+                    )
+                })
+            })
+            .collect()
     }
 
-    pub fn should_shoot(&self) -> bool {
-        self.weapon_cooldown == 0.0 && self.actions.is_shooting
+    /// The range of the beam the weapon in use is firing, if it is a beam weapon and
+    /// its trigger is held.
+    pub fn beam_range(&self) -> Option<f64> {
+        self.weapon()
+            .beam_range()
+            .filter(|_| self.actions.is_shooting)
+    }
+
+    /// The tip of the ship, where beams are fired from.
+    pub fn nose(&self) -> Vector {
+        self.pos
+            + Vector {
+                x: self.rot.cos(),
+                y: self.rot.sin(),
+            } * self.radius()
+    }
+
+    /// Stops the player from being hit for `duration` seconds.
+    pub fn make_invulnerable(&mut self, duration: f64) {
+        self.invulnerability = duration;
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerability > 0.0
+    }
+
+    /// Whether the hyperspace drive has finished charging and the ship should jump.
+    pub fn hyperspace_ready(&self) -> bool {
+        self.hyperspace_charge.is_some_and(|charge| charge <= 0.0)
+    }
+
+    /// Jumps the ship to `destination`, where it arrives at rest.
+    pub fn hyperspace(&mut self, destination: Vector) {
+        self.pos = destination;
+        self.vel = Vector { x: 0.0, y: 0.0 };
+        self.hyperspace_charge = None;
+        self.hyperspace_cooldown = HYPERSPACE_COOLDOWN;
+        self.hyperspace_arrival = HYPERSPACE_ARRIVAL;
+    }
+
+    /// Energy left in the shield, from 0 to `MAX_ENERGY`.
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    /// Whether the ship is still bouncing away from an impact the shield absorbed.
+    pub fn is_bouncing(&self) -> bool {
+        self.impact > 0.0
+    }
+
+    /// Tries to absorb the impact of an asteroid at `position` moving at `velocity`,
+    /// using energy in proportion to how large and how fast the asteroid is relative
+    /// to the ship. If there is enough energy the ship bounces off, otherwise the
+    /// shield collapses. Returns whether the impact was absorbed.
+    pub fn absorb_impact(
+        &mut self,
+        position: Vector,
+        velocity: Vector,
+        radius: f64,
+        bounds: Size,
+    ) -> bool {
+        let offset = position.wrapped_delta(self.pos, bounds.into());
+        let normal = offset / offset.magnitude().max(f64::EPSILON);
+        let mut relative = self.vel - velocity;
+
+        let cost = relative.magnitude().max(MIN_IMPACT_SPEED) * radius * IMPACT_COST;
+        if cost > self.energy {
+            self.energy = 0.0;
+            return false;
+        }
+        self.energy -= cost;
+
+        // Reflect the ship off the asteroid, making sure it moves away fast enough
+        // not to be caught by it again.
+        let approach = relative.dot(normal);
+        if approach < 0.0 {
+            relative -= normal * (2.0 * approach);
+        }
+        relative += normal * (MIN_BOUNCE_SPEED - relative.dot(normal)).max(0.0);
+        self.vel = velocity + relative;
+        self.impact = IMPACT_TIME;
+        true
+    }
+
+    /// The power-ups currently affecting the player.
+    pub fn effects(&self) -> &Effects {
+        &self.effects
+    }
+
+    /// Starts the effect of a power-up the player picked up.
+    pub fn power_up(&mut self, kind: Kind) {
+        self.effects.start(kind);
+    }
+
+    /// Draws a small ship pointing up at the origin of `context`, used to show lives.
+    pub fn draw_life_icon<G: Graphics>(context: Context, graphics: &mut G) {
+        polygon(
+            color::CYAN,
+            SHIP,
+            context
+                .transform
+                .rot_rad(-f64::consts::FRAC_PI_2)
+                .scale(0.75, 0.75),
+            graphics,
+        );
     }
 }
 
 impl Updateable for Player {
-    fn update(&mut self, args: UpdateArgs) {
-        self.pos += self.vel + self.window_size.into();
-        self.pos %= self.window_size.into();
+    fn update(&mut self, args: UpdateArgs, bounds: Size) {
+        self.pos += self.vel * args.dt + bounds.into();
+        self.pos %= bounds.into();
 
         if self.actions.rotate_cw {
             self.rotate_cw(args.dt)
@@ -116,28 +362,71 @@ impl Updateable for Player {
             self.fire_forward_boosters(args.dt)
         }
 
+        // Switch to the next slot when switching is first held, dropping any charge.
+        if self.actions.switch_weapon && !self.switching {
+            self.weapon = (self.weapon + 1) % self.weapons.len();
+            self.charge = 0.0;
+        }
+        self.switching = self.actions.switch_weapon;
+
         if self.weapon_cooldown > 0.0 {
             self.weapon_cooldown = (self.weapon_cooldown - args.dt).max(0.0);
         }
+        // A charging weapon charges while its trigger is held once it has cooled down.
+        let max_charge = self.weapon().max_charge();
+        if max_charge > 0.0 && self.actions.is_shooting && self.weapon_cooldown == 0.0 {
+            self.charge = (self.charge + args.dt).min(max_charge);
+        }
+        if self.invulnerability > 0.0 {
+            self.invulnerability = (self.invulnerability - args.dt).max(0.0);
+        }
+        self.effects.update(args.dt);
+        self.energy = (self.energy + ENERGY_RECHARGE * args.dt).min(MAX_ENERGY);
+        self.impact = (self.impact - args.dt).max(0.0);
+
+        // Holding the hyperspace key starts charging the drive once it has cooled down.
+        self.hyperspace_cooldown = (self.hyperspace_cooldown - args.dt).max(0.0);
+        self.hyperspace_arrival = (self.hyperspace_arrival - args.dt).max(0.0);
+        self.hyperspace_charge = match self.hyperspace_charge {
+            Some(charge) => Some(charge - args.dt),
+            None if self.actions.hyperspace && self.hyperspace_cooldown == 0.0 => {
+                Some(HYPERSPACE_CHARGE)
+            }
+            None => None,
+        };
     }
 }
 
 const SHIP_HEIGHT: f64 = 16.0;
 const SHIP_WIDTH: f64 = 20.0;
 const SHIP: &types::Triangle = &[
-    [0.0, -1.0 * SHIP_HEIGHT / 2.0],
+    [0.0, -SHIP_HEIGHT / 2.0],
     [SHIP_WIDTH, 0.0],
     [0.0, SHIP_HEIGHT / 2.0],
 ];
+const BOOSTER_HEIGHT: f64 = 8.0;
+const BOOSTER_WIDTH: f64 = 10.0;
+const BOOSTER: &types::Triangle = &[
+    [0.0, -BOOSTER_HEIGHT / 2.0],
+    [BOOSTER_WIDTH, 0.0],
+    [0.0, BOOSTER_HEIGHT / 2.0],
+];
+
+/// The radius of the ring drawn around the ship while shielded.
+const SHIELD_RADIUS: f64 = 22.0;
+
+/// The radius of the rings drawn while jumping through hyperspace.
+const HYPERSPACE_RADIUS: f64 = 30.0;
+
+/// How many times per second the ship blinks while invulnerable.
+const BLINK_RATE: f64 = 8.0;
+
 impl Drawable for Player {
-    fn draw(&self, context: Context, graphics: &mut GlGraphics) {
-        const BOOSTER_HEIGHT: f64 = 8.0;
-        const BOOSTER_WIDTH: f64 = 10.0;
-        const BOOSTER: &types::Triangle = &[
-            [0.0, -1.0 * BOOSTER_HEIGHT / 2.0],
-            [BOOSTER_WIDTH, 0.0],
-            [0.0, BOOSTER_HEIGHT / 2.0],
-        ];
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
+        // Blink while invulnerable by skipping every other interval.
+        if self.is_invulnerable() && (self.invulnerability * BLINK_RATE * 2.0) as u64 % 2 == 1 {
+            return;
+        }
 
         // Draw the boosters first, so that they look like they are coming
         // from underneath the ship.
@@ -196,9 +485,50 @@ impl Drawable for Player {
                 .rot_rad(self.rot)
                 // Without this trans(), rotation occurs around the
                 // upper left corner rather than the center.
-                .trans(-1.0 * SHIP_HEIGHT / 2.0, 0.0),
+                .trans(-SHIP_HEIGHT / 2.0, 0.0),
             graphics,
         );
+        // A ring closes in on the ship as the hyperspace drive charges, and another
+        // spreads out from where it arrives.
+        let hyperspace_ring = match self.hyperspace_charge {
+            Some(charge) => Some(charge.max(0.0) / HYPERSPACE_CHARGE),
+            None if self.hyperspace_arrival > 0.0 => {
+                Some(1.0 - self.hyperspace_arrival / HYPERSPACE_ARRIVAL)
+            }
+            None => None,
+        };
+        if let Some(fraction) = hyperspace_ring {
+            Ellipse::new_border(color::WHITE, 1.0).draw(
+                ellipse::circle(0.0, 0.0, HYPERSPACE_RADIUS * fraction),
+                &context.draw_state,
+                context.transform.trans(self.pos.x, self.pos.y),
+                graphics,
+            );
+        }
+        // The shield bubble fades as it loses energy and flashes when it absorbs
+        // an impact. The shield power-up keeps it at full strength.
+        let mut shield_color = Kind::Shield.color();
+        if !self.effects.is_active(Kind::Shield) && !self.is_bouncing() {
+            shield_color[3] = (0.6 * self.energy / MAX_ENERGY) as f32;
+        }
+        if shield_color[3] > 0.0 {
+            Ellipse::new_border(shield_color, 1.0).draw(
+                ellipse::circle(0.0, 0.0, SHIELD_RADIUS),
+                &context.draw_state,
+                context.transform.trans(self.pos.x, self.pos.y),
+                graphics,
+            );
+        }
+    }
+
+    /// The shield bubble reaches furthest from the center, unless a hyperspace ring
+    /// is drawn around it.
+    fn draw_radius(&self) -> f64 {
+        if self.hyperspace_charge.is_some() || self.hyperspace_arrival > 0.0 {
+            HYPERSPACE_RADIUS
+        } else {
+            SHIELD_RADIUS
+        }
     }
 }
 
@@ -209,7 +539,14 @@ impl Positioned for Player {
 }
 
 impl Collidable for Player {
+    /// The nose of the ship is the point furthest from its center.
     fn radius(&self) -> f64 {
-        SHIP_WIDTH / 2.0
+        SHIP_WIDTH - SHIP_HEIGHT / 2.0
+    }
+
+    fn outline(&self) -> Option<Vec<Vector>> {
+        // Centered the same way as when drawing the ship.
+        let centered = SHIP.map(|vertex| [vertex[0] - SHIP_HEIGHT / 2.0, vertex[1]]);
+        Some(geometry::to_world(&centered, self.pos, self.rot))
     }
 }
@@ -0,0 +1,80 @@
+//! Defines the score popups.
+//!
+//! A `ScorePopup` shows the points an asteroid was worth where it was destroyed,
+//! then floats upward and fades out.
+
+use std::fmt::Debug;
+
+use piston_window::character::CharacterCache;
+use piston_window::{text, Context, Graphics, Size, Transformed, UpdateArgs};
+
+use crate::game::color;
+use crate::game::models::vector::Vector;
+use crate::game::models::Updateable;
+
+/// Seconds a popup is shown for.
+const POPUP_DURATION: f64 = 1.0;
+
+/// How fast a popup floats upward, in pixels per second.
+const RISE_SPEED: f64 = 30.0;
+
+const FONT_SIZE: u32 = 16;
+
+pub struct ScorePopup {
+    pos: Vector,
+    points: i64,
+    ttl: f64,
+}
+
+impl ScorePopup {
+    /// Creates a popup showing `points` at `position`.
+    pub fn new(position: Vector, points: i64, bounds: Size) -> Self {
+        // Asteroids destroyed before they have fully entered the screen still show
+        // their points on the screen.
+        let bounds = Vector::from(bounds);
+        ScorePopup {
+            pos: Vector {
+                x: position.x.rem_euclid(bounds.x),
+                y: position.y.rem_euclid(bounds.y),
+            },
+            points,
+            ttl: POPUP_DURATION,
+        }
+    }
+
+    pub fn ttl(&self) -> f64 {
+        self.ttl
+    }
+
+    /// Draws the points centered on the popup's position, fading out as it expires.
+    pub fn draw<G, C>(&self, context: Context, graphics: &mut G, glyph_cache: &mut C)
+    where
+        G: Graphics,
+        C: CharacterCache<Texture = G::Texture>,
+        C::Error: Debug,
+    {
+        let mut popup_color = color::YELLOW;
+        popup_color[3] = (self.ttl / POPUP_DURATION) as f32;
+
+        let label = self.points.to_string();
+        let offset = (label.len() * FONT_SIZE as usize / 4) as f64;
+        text(
+            popup_color,
+            FONT_SIZE,
+            &label,
+            glyph_cache,
+            context
+                .transform
+                .trans(self.pos.x - offset, self.pos.y + f64::from(FONT_SIZE) / 2.0),
+            graphics,
+        )
+        .unwrap();
+    }
+}
+
+impl Updateable for ScorePopup {
+    fn update(&mut self, args: UpdateArgs, _: Size) {
+        self.pos.y -= RISE_SPEED * args.dt;
+        self.ttl -= args.dt;
+    }
+}
@@ -0,0 +1,251 @@
+//! Defines the power-up component.
+//!
+//! `PowerUp`s are sometimes left behind by destroyed asteroids. They drift
+//! slowly until the player flies into them or they expire. Most give the
+//! player a temporary effect, which is tracked by the player's `Effects`.
+
+use piston_window::{polygon, types, Context, Graphics, Size, Transformed, UpdateArgs};
+use rand::Rng;
+
+use crate::game::color;
+use crate::game::models::vector::Vector;
+use crate::game::models::{Collidable, Drawable, Positioned, Updateable};
+
+/// Seconds a power-up can be picked up for before it disappears.
+const LIFETIME: f64 = 10.0;
+
+/// A power-up blinks for this many seconds before disappearing.
+const EXPIRY_WARNING: f64 = 3.0;
+
+/// The most a power-up drifts, in pixels per second along each axis.
+const MAX_DRIFT: f64 = 20.0;
+
+const POWERUP_SIZE: f64 = 10.0;
+const POWERUP: &[[f64; 2]] = &[
+    [0.0, -POWERUP_SIZE],
+    [POWERUP_SIZE, 0.0],
+    [0.0, POWERUP_SIZE],
+    [-POWERUP_SIZE, 0.0],
+];
+
+/// The kinds of power-up that can be picked up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Protects the player from asteroids.
+    Shield,
+
+    /// Shortens the time between shots.
+    RapidFire,
+
+    /// Fires three bullets in a fan instead of one.
+    SpreadShot,
+
+    /// Bullets pass through the asteroids they destroy.
+    Piercing,
+
+    /// Gives the player another life straight away.
+    ExtraLife,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] = [
+        Kind::Shield,
+        Kind::RapidFire,
+        Kind::SpreadShot,
+        Kind::Piercing,
+        Kind::ExtraLife,
+    ];
+
+    /// Picks a kind at random, with common kinds picked more often than rare ones.
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let total: u32 = Kind::ALL.iter().map(|kind| kind.weight()).sum();
+        let mut roll = rng.gen_range(0..total);
+        for kind in Kind::ALL {
+            if roll < kind.weight() {
+                return kind;
+            }
+            roll -= kind.weight();
+        }
+        unreachable!("the roll is always below the total weight")
+    }
+
+    /// How likely this kind is to be picked, relative to the others.
+    fn weight(self) -> u32 {
+        match self {
+            Kind::Shield => 3,
+            Kind::RapidFire => 4,
+            Kind::SpreadShot => 3,
+            Kind::Piercing => 2,
+            Kind::ExtraLife => 1,
+        }
+    }
+
+    /// Seconds the effect lasts for, or 0 for effects that are applied once.
+    pub fn duration(self) -> f64 {
+        match self {
+            Kind::Shield => 8.0,
+            Kind::RapidFire => 10.0,
+            Kind::SpreadShot => 10.0,
+            Kind::Piercing => 8.0,
+            Kind::ExtraLife => 0.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Shield => "Shield",
+            Kind::RapidFire => "Rapid Fire",
+            Kind::SpreadShot => "Spread Shot",
+            Kind::Piercing => "Piercing",
+            Kind::ExtraLife => "Extra Life",
+        }
+    }
+
+    pub fn color(self) -> types::Color {
+        match self {
+            Kind::Shield => color::CYAN,
+            Kind::RapidFire => color::YELLOW,
+            Kind::SpreadShot => color::MAGENTA,
+            Kind::Piercing => color::WHITE,
+            Kind::ExtraLife => color::GREEN,
+        }
+    }
+}
+
+/// Seconds left on each timed power-up the player has picked up.
+#[derive(Default)]
+pub struct Effects {
+    shield: f64,
+    rapid_fire: f64,
+    spread_shot: f64,
+    piercing: f64,
+}
+
+impl Effects {
+    fn timer(&mut self, kind: Kind) -> Option<&mut f64> {
+        match kind {
+            Kind::Shield => Some(&mut self.shield),
+            Kind::RapidFire => Some(&mut self.rapid_fire),
+            Kind::SpreadShot => Some(&mut self.spread_shot),
+            Kind::Piercing => Some(&mut self.piercing),
+            Kind::ExtraLife => None,
+        }
+    }
+
+    /// Starts the effect of `kind`, or restarts it if it is already active.
+    pub fn start(&mut self, kind: Kind) {
+        if let Some(timer) = self.timer(kind) {
+            *timer = kind.duration();
+        }
+    }
+
+    pub fn is_active(&self, kind: Kind) -> bool {
+        self.remaining(kind) > 0.0
+    }
+
+    /// Seconds left until the effect of `kind` ends.
+    pub fn remaining(&self, kind: Kind) -> f64 {
+        match kind {
+            Kind::Shield => self.shield,
+            Kind::RapidFire => self.rapid_fire,
+            Kind::SpreadShot => self.spread_shot,
+            Kind::Piercing => self.piercing,
+            Kind::ExtraLife => 0.0,
+        }
+    }
+
+    /// Every active effect and the seconds left until it ends.
+    pub fn active(&self) -> impl Iterator<Item = (Kind, f64)> + '_ {
+        Kind::ALL
+            .into_iter()
+            .map(|kind| (kind, self.remaining(kind)))
+            .filter(|&(_, remaining)| remaining > 0.0)
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        for timer in [
+            &mut self.shield,
+            &mut self.rapid_fire,
+            &mut self.spread_shot,
+            &mut self.piercing,
+        ] {
+            *timer = (*timer - dt).max(0.0);
+        }
+    }
+}
+
+pub struct PowerUp {
+    pos: Vector,
+    vel: Vector,
+    kind: Kind,
+    ttl: f64,
+}
+
+impl PowerUp {
+    /// Creates a power-up of a random kind drifting away from `position`.
+    pub fn new<R: Rng>(position: Vector, bounds: Size, rng: &mut R) -> Self {
+        // Power-ups dropped by asteroids that have not fully entered the screen yet
+        // still appear on the screen.
+        let bounds = Vector::from(bounds);
+        PowerUp {
+            pos: Vector {
+                x: position.x.rem_euclid(bounds.x),
+                y: position.y.rem_euclid(bounds.y),
+            },
+            vel: Vector::new_rand(rng, -MAX_DRIFT, -MAX_DRIFT, MAX_DRIFT, MAX_DRIFT),
+            kind: Kind::random(rng),
+            ttl: LIFETIME,
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn ttl(&self) -> f64 {
+        self.ttl
+    }
+}
+
+impl Updateable for PowerUp {
+    fn update(&mut self, args: UpdateArgs, bounds: Size) {
+        self.pos += self.vel * args.dt + bounds.into();
+        self.pos %= bounds.into();
+        self.ttl -= args.dt;
+    }
+}
+
+impl Drawable for PowerUp {
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
+        // Blink a few times per second when about to expire.
+        if self.ttl < EXPIRY_WARNING && (self.ttl * 4.0) as u64 % 2 == 1 {
+            return;
+        }
+
+        polygon(
+            self.kind.color(),
+            POWERUP,
+            context
+                .transform
+                .trans(self.pos.x, self.pos.y)
+                .rot_rad(LIFETIME - self.ttl),
+            graphics,
+        );
+    }
+
+    fn draw_radius(&self) -> f64 {
+        POWERUP_SIZE
+    }
+}
+
+impl Positioned for PowerUp {
+    fn pos(&self) -> Vector {
+        self.pos
+    }
+}
+
+impl Collidable for PowerUp {
+    fn radius(&self) -> f64 {
+        POWERUP_SIZE
+    }
+}
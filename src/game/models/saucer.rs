@@ -0,0 +1,370 @@
+//! Defines the saucer component.
+//!
+//! `Saucer`s are hostile ships that hunt the player. Their movement is decided
+//! by an `ai_behavior` behavior tree made of `Maneuver`s: a saucer patrols,
+//! approaches the player and circles them while shooting, then breaks away.
+//! Whenever the player comes too close, it flees. After a while it gives up the
+//! hunt and leaves the playfield through the nearest side edge.
+
+use std::f64;
+
+use ai_behavior::{
+    Action, ActionArgs, Behavior, State, Success, WaitForever, WhenAny, While, RUNNING,
+};
+use piston_window::{polygon, Context, Event, Graphics, Loop, Size, Transformed, UpdateArgs};
+use rand::Rng;
+
+use crate::game::color;
+use crate::game::models::bullet::{Bullet, Projectile};
+use crate::game::models::geometry;
+use crate::game::models::vector::Vector;
+use crate::game::models::{Collidable, Drawable, Positioned, Updateable, PI_MULT_2};
+
+/// The outline of a large saucer, used for collisions. It is drawn as a hull with a dome on top.
+const SAUCER: &[[f64; 2]] = &[
+    [-20.0, 0.0],
+    [-8.0, -6.0],
+    [-4.0, -12.0],
+    [4.0, -12.0],
+    [8.0, -6.0],
+    [20.0, 0.0],
+    [8.0, 6.0],
+    [-8.0, 6.0],
+];
+const HULL: &[[f64; 2]] = &[
+    [-20.0, 0.0],
+    [-8.0, -6.0],
+    [8.0, -6.0],
+    [20.0, 0.0],
+    [8.0, 6.0],
+    [-8.0, 6.0],
+];
+const DOME: &[[f64; 2]] = &[[-8.0, -6.0], [-4.0, -12.0], [4.0, -12.0], [8.0, -6.0]];
+
+/// The distance from the center of a large saucer to its furthest vertex.
+const SAUCER_RADIUS: f64 = 20.0;
+
+/// A saucer approaches the player until it is this close, then starts circling them.
+const STRAFE_DISTANCE: f64 = 200.0;
+
+/// A saucer flees when the player comes closer than this.
+const FLEE_DISTANCE: f64 = 100.0;
+
+/// Seconds each timed maneuver lasts.
+const PATROL_TIME: f64 = 3.0;
+const STRAFE_TIME: f64 = 4.0;
+const FLEE_TIME: f64 = 1.5;
+
+/// Seconds a saucer hunts the player before it leaves.
+const LIFETIME: f64 = 20.0;
+
+/// The most a small saucer misses its aim by, in radians either way.
+const AIM_ERROR: f64 = 0.15;
+
+/// The two kinds of saucer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SaucerSize {
+    /// Slow, easy to hit and fires in random directions.
+    Large,
+
+    /// Fast, hard to hit and aims at the player.
+    Small,
+}
+
+impl SaucerSize {
+    fn scale(self) -> f64 {
+        match self {
+            SaucerSize::Large => 1.0,
+            SaucerSize::Small => 0.6,
+        }
+    }
+
+    /// Speed in pixels per second.
+    fn speed(self) -> f64 {
+        match self {
+            SaucerSize::Large => 80.0,
+            SaucerSize::Small => 130.0,
+        }
+    }
+
+    /// The points awarded for destroying a saucer of this size.
+    pub fn points(self) -> i64 {
+        match self {
+            SaucerSize::Large => 200,
+            SaucerSize::Small => 1000,
+        }
+    }
+
+    /// Seconds between shots.
+    fn weapon_cooldown(self) -> f64 {
+        match self {
+            SaucerSize::Large => 1.5,
+            SaucerSize::Small => 1.0,
+        }
+    }
+}
+
+/// The actions a saucer's behavior tree is made of.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Maneuver {
+    /// Keeps a steady course for some seconds.
+    Patrol(f64),
+
+    /// Heads for the player until close enough to strafe them.
+    Approach,
+
+    /// Circles the player for some seconds.
+    Strafe(f64),
+
+    /// Heads away from the player for some seconds.
+    Flee(f64),
+
+    /// Keeps watch until the player comes too close.
+    Watch,
+}
+
+/// Patrols, approaches and strafes the player over and over, breaking away after every
+/// pass or as soon as the player comes too close.
+fn behavior() -> Behavior<Maneuver> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            WhenAny(vec![
+                Action(Maneuver::Watch),
+                ai_behavior::Sequence(vec![
+                    Action(Maneuver::Patrol(PATROL_TIME)),
+                    Action(Maneuver::Approach),
+                    Action(Maneuver::Strafe(STRAFE_TIME)),
+                ]),
+            ]),
+            Action(Maneuver::Flee(FLEE_TIME)),
+        ],
+    )
+}
+
+pub struct Saucer {
+    pos: Vector,
+    size: SaucerSize,
+
+    /// The direction the saucer is flying in, in radians.
+    heading: f64,
+    weapon_cooldown: f64,
+
+    /// The state of the saucer's behavior tree, with the seconds spent on the current maneuver.
+    brain: State<Maneuver, f64>,
+
+    /// Seconds left until the saucer leaves.
+    lifetime: f64,
+}
+
+impl Saucer {
+    /// Creates a saucer at a random point of the left or right edge of the playfield.
+    pub fn new<R: Rng>(size: SaucerSize, bounds: Size, rng: &mut R) -> Self {
+        let from_left = rng.gen::<bool>();
+        Saucer {
+            pos: Vector {
+                x: if from_left { 0.0 } else { bounds.width },
+                y: rng.gen::<f64>() * bounds.height,
+            },
+            size,
+            heading: if from_left { 0.0 } else { f64::consts::PI },
+            weapon_cooldown: size.weapon_cooldown(),
+            brain: State::new(behavior()),
+            lifetime: LIFETIME,
+        }
+    }
+
+    pub fn size(&self) -> SaucerSize {
+        self.size
+    }
+
+    /// Whether the saucer has stopped hunting and is flying off the playfield.
+    fn is_leaving(&self) -> bool {
+        self.lifetime <= 0.0
+    }
+
+    /// Whether the saucer has left the playfield entirely and can be removed.
+    pub fn has_left(&self, bounds: Size) -> bool {
+        let radius = self.radius();
+        self.is_leaving() && (self.pos.x < -radius || self.pos.x > bounds.width + radius)
+    }
+
+    /// Runs the behavior tree for `dt` seconds, steering towards or away from `target`,
+    /// the position of the player, if there is one. A leaving saucer keeps its course.
+    pub fn think(&mut self, dt: f64, target: Option<Vector>, bounds: Size) {
+        if self.is_leaving() {
+            return;
+        }
+        let event = Event::Loop(Loop::Update(UpdateArgs { dt }));
+        let offset = target.map(|target| self.pos.wrapped_delta(target, bounds.into()));
+        let heading = &mut self.heading;
+        self.brain
+            .event(&event, &mut |args: ActionArgs<Event, Maneuver, f64>| {
+                let elapsed = args.state.get_or_insert(0.0);
+                *elapsed += args.dt;
+                let timed = |duration: f64| {
+                    if *elapsed >= duration {
+                        (Success, *elapsed - duration)
+                    } else {
+                        RUNNING
+                    }
+                };
+                let towards_target = offset.map(|offset| offset.y.atan2(offset.x));
+                match (*args.action, offset, towards_target) {
+                    (Maneuver::Patrol(duration), _, _) => timed(duration),
+                    (Maneuver::Approach, Some(offset), Some(angle))
+                        if offset.magnitude() > STRAFE_DISTANCE =>
+                    {
+                        *heading = angle;
+                        RUNNING
+                    }
+                    (Maneuver::Approach, _, _) => (Success, args.dt),
+                    (Maneuver::Strafe(duration), _, Some(angle)) => {
+                        *heading = angle + f64::consts::FRAC_PI_2;
+                        timed(duration)
+                    }
+                    (Maneuver::Flee(duration), _, Some(angle)) => {
+                        *heading = angle + f64::consts::PI;
+                        timed(duration)
+                    }
+                    (Maneuver::Strafe(duration) | Maneuver::Flee(duration), _, None) => {
+                        timed(duration)
+                    }
+                    (Maneuver::Watch, Some(offset), _) if offset.magnitude() < FLEE_DISTANCE => {
+                        (Success, args.dt)
+                    }
+                    (Maneuver::Watch, _, _) => RUNNING,
+                }
+            });
+    }
+
+    /// Fires a bullet once the weapon is ready. Large saucers fire in a random direction,
+    /// while small saucers aim at `target`, the position of the player, if there is one.
+    pub fn shoot<R: Rng>(
+        &mut self,
+        target: Option<Vector>,
+        bounds: Size,
+        rng: &mut R,
+    ) -> Option<Bullet> {
+        if self.weapon_cooldown > 0.0 {
+            return None;
+        }
+        let direction = match (self.size, target) {
+            (SaucerSize::Small, Some(target)) => {
+                let offset = self.pos.wrapped_delta(target, bounds.into());
+                offset.y.atan2(offset.x) + (rng.gen::<f64>() - 0.5) * 2.0 * AIM_ERROR
+            }
+            (SaucerSize::Small, None) => return None,
+            (SaucerSize::Large, _) => rng.gen::<f64>() * PI_MULT_2,
+        };
+        self.weapon_cooldown = self.size.weapon_cooldown();
+        Some(Bullet::new(
+            self.pos,
+            self.velocity(),
+            direction,
+            Projectile::STANDARD,
+        ))
+    }
+
+    fn velocity(&self) -> Vector {
+        Vector {
+            x: self.heading.cos(),
+            y: self.heading.sin(),
+        } * self.size.speed()
+    }
+}
+
+impl Updateable for Saucer {
+    fn update(&mut self, args: UpdateArgs, bounds: Size) {
+        if !self.is_leaving() {
+            self.lifetime -= args.dt;
+            if self.is_leaving() {
+                // Head for the nearest side edge and fly through it.
+                self.heading = if self.pos.x < bounds.width / 2.0 {
+                    f64::consts::PI
+                } else {
+                    0.0
+                };
+            }
+        }
+        if self.is_leaving() {
+            self.pos += self.velocity() * args.dt;
+        } else {
+            self.pos += self.velocity() * args.dt + bounds.into();
+            self.pos %= bounds.into();
+        }
+        self.weapon_cooldown = (self.weapon_cooldown - args.dt).max(0.0);
+    }
+}
+
+impl Drawable for Saucer {
+    fn draw<G: Graphics>(&self, context: Context, graphics: &mut G) {
+        let scale = self.size.scale();
+        let transform = context
+            .transform
+            .trans(self.pos.x, self.pos.y)
+            .scale(scale, scale);
+        polygon(color::GREEN, DOME, transform, graphics);
+        polygon(color::GRAY, HULL, transform, graphics);
+    }
+
+    fn draw_radius(&self) -> f64 {
+        SAUCER_RADIUS * self.size.scale()
+    }
+}
+
+impl Positioned for Saucer {
+    fn pos(&self) -> Vector {
+        self.pos
+    }
+
+    /// A leaving saucer flies off the playfield instead of wrapping around.
+    fn wraps(&self) -> bool {
+        !self.is_leaving()
+    }
+}
+
+impl Collidable for Saucer {
+    fn radius(&self) -> f64 {
+        SAUCER_RADIUS * self.size.scale()
+    }
+
+    fn outline(&self) -> Option<Vec<Vector>> {
+        let scale = self.size.scale();
+        let scaled = SAUCER.iter().map(|&[x, y]| [x * scale, y * scale]);
+        Some(geometry::to_world(
+            &scaled.collect::<Vec<_>>(),
+            self.pos,
+            0.0,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use piston_window::UpdateArgs;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{Saucer, SaucerSize, LIFETIME};
+    use crate::game::models::testing::BOUNDS;
+    use crate::game::models::vector::Vector;
+    use crate::game::models::{Positioned, Updateable};
+
+    #[test]
+    fn leaves_the_playfield_after_its_lifetime() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut saucer = Saucer::new(SaucerSize::Large, BOUNDS, &mut rng);
+        let target = Some(Vector { x: 400.0, y: 300.0 });
+        let dt = 1.0 / 120.0;
+        let mut elapsed = 0.0;
+        while !saucer.has_left(BOUNDS) {
+            assert!(elapsed < LIFETIME + 10.0, "the saucer never left");
+            saucer.think(dt, target, BOUNDS);
+            saucer.update(UpdateArgs { dt }, BOUNDS);
+            elapsed += dt;
+        }
+        assert!(elapsed >= LIFETIME);
+        assert!(!saucer.wraps());
+    }
+}
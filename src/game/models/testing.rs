@@ -0,0 +1,67 @@
+//! Stand-ins shared by the tests of the game's models and simulation.
+
+use piston_window::Size;
+
+use crate::game::models::geometry;
+use crate::game::models::vector::Vector;
+use crate::game::models::{Collidable, Positioned};
+
+/// The size of the playfield used in tests.
+pub const BOUNDS: Size = Size {
+    width: 800.0,
+    height: 600.0,
+};
+
+/// A `Collidable` that can be placed anywhere, either round or square.
+pub struct Shape {
+    pub pos: Vector,
+    pub radius: f64,
+    pub wraps: bool,
+
+    /// Whether the shape is a square with an outline rather than a circle.
+    square: bool,
+}
+
+impl Shape {
+    /// A circle with a radius of 10 pixels.
+    pub fn circle(x: f64, y: f64) -> Self {
+        Shape {
+            pos: Vector { x, y },
+            radius: 10.0,
+            wraps: true,
+            square: false,
+        }
+    }
+
+    /// A square 10 pixels wide.
+    pub fn square(x: f64, y: f64) -> Self {
+        Shape {
+            pos: Vector { x, y },
+            radius: 50.0_f64.sqrt(),
+            wraps: true,
+            square: true,
+        }
+    }
+}
+
+impl Positioned for Shape {
+    fn pos(&self) -> Vector {
+        self.pos
+    }
+
+    fn wraps(&self) -> bool {
+        self.wraps
+    }
+}
+
+impl Collidable for Shape {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn outline(&self) -> Option<Vec<Vector>> {
+        let corners = [[-5.0, -5.0], [5.0, -5.0], [5.0, 5.0], [-5.0, 5.0]];
+        self.square
+            .then(|| geometry::to_world(&corners, self.pos, 0.0))
+    }
+}
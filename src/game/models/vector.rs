@@ -2,6 +2,7 @@ use std::f64::consts::PI;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
 
 use piston_window::Size;
+use rand::Rng;
 
 /// Models an (x, y) coordinate value (such as position or velocity).
 #[derive(Copy, Clone, Default)]
@@ -11,10 +12,10 @@ pub struct Vector {
 }
 
 impl Vector {
-    pub fn new_rand(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Self {
+    pub fn new_rand<R: Rng>(rng: &mut R, x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Self {
         Vector {
-            x: rand::random::<f64>() * (x_max - x_min) + x_min,
-            y: rand::random::<f64>() * (y_max - y_min) + y_min,
+            x: rng.gen::<f64>() * (x_max - x_min) + x_min,
+            y: rng.gen::<f64>() * (y_max - y_min) + y_min,
         }
     }
     pub fn angle_to_vector(self, other: Vector) -> f64 {
@@ -35,10 +36,24 @@ impl Vector {
         (self.x.powi(2) + self.y.powi(2)).sqrt()
     }
 
+    pub fn dot(self, other: Vector) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
     pub fn distance(self, other: Vector) -> f64 {
         (self - other).magnitude()
     }
 
+    /// The shortest offset from this position to `other` on a playfield that wraps
+    /// around at `bounds`, going across its edges if that is shorter.
+    pub fn wrapped_delta(self, other: Vector, bounds: Vector) -> Vector {
+        let diff = other - self;
+        Vector {
+            x: diff.x - bounds.x * (diff.x / bounds.x).round(),
+            y: diff.y - bounds.y * (diff.y / bounds.y).round(),
+        }
+    }
+
     pub fn rotate(self, angle: f64) -> Vector {
         let old_angle = (self.angle_to_vector(Vector { x: 0.0, y: 0.0 }) + PI) % (PI * 2.0);
         let magnitude = self.magnitude();
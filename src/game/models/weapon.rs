@@ -0,0 +1,210 @@
+//! Defines the ship's weapons.
+//!
+//! A `Weapon` decides how often the ship can fire and which `Projectile`s each
+//! shot is made of. The ship carries one of each
+//! weapon in its slots and the player switches between them while flying.
+
+use crate::game::models::bullet::Projectile;
+
+/// Identifies each of the weapons, so that the game can tell which one was fired.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WeaponKind {
+    Blaster,
+    SpreadGun,
+    RapidFireCannon,
+    ChargedShot,
+    Laser,
+}
+
+/// A single projectile fired by a weapon.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shot {
+    /// The direction of the shot relative to the ship's heading, in radians.
+    pub angle: f64,
+    pub projectile: Projectile,
+}
+
+/// Something the ship can fire.
+pub trait Weapon {
+    fn name(&self) -> &'static str;
+
+    /// Seconds between shots.
+    fn cooldown(&self) -> f64;
+
+    fn kind(&self) -> WeaponKind;
+
+    /// Seconds the trigger can be held to charge a shot, which is fired on release.
+    /// Weapons that do not charge fire as long as the trigger is held.
+    fn max_charge(&self) -> f64 {
+        0.0
+    }
+
+    /// How far the continuous beam of a beam weapon reaches, in pixels. Beam weapons
+    /// fire for as long as the trigger is held instead of firing shots.
+    fn beam_range(&self) -> Option<f64> {
+        None
+    }
+
+    /// The projectiles making up one shot. `charge` is how much of `max_charge`
+    /// the shot was charged for, from 0 to 1.
+    fn fire(&self, charge: f64) -> Vec<Shot>;
+}
+
+/// The ship's original gun, firing single bullets.
+pub struct Blaster;
+
+impl Weapon for Blaster {
+    fn name(&self) -> &'static str {
+        "Blaster"
+    }
+
+    fn cooldown(&self) -> f64 {
+        0.25
+    }
+
+    fn kind(&self) -> WeaponKind {
+        WeaponKind::Blaster
+    }
+
+    fn fire(&self, _: f64) -> Vec<Shot> {
+        vec![Shot {
+            angle: 0.0,
+            projectile: Projectile::STANDARD,
+        }]
+    }
+}
+
+/// Fires a fan of short-lived bullets.
+pub struct SpreadGun;
+
+/// The angles the spread gun's bullets are fired at, in radians.
+const SPREAD_GUN_ANGLES: [f64; 5] = [-0.3, -0.15, 0.0, 0.15, 0.3];
+
+impl Weapon for SpreadGun {
+    fn name(&self) -> &'static str {
+        "Spread Gun"
+    }
+
+    fn cooldown(&self) -> f64 {
+        0.4
+    }
+
+    fn kind(&self) -> WeaponKind {
+        WeaponKind::SpreadGun
+    }
+
+    fn fire(&self, _: f64) -> Vec<Shot> {
+        SPREAD_GUN_ANGLES
+            .iter()
+            .map(|&angle| Shot {
+                angle,
+                projectile: Projectile {
+                    ttl: 0.6,
+                    ..Projectile::STANDARD
+                },
+            })
+            .collect()
+    }
+}
+
+/// Fires a stream of small, fast bullets.
+pub struct RapidFireCannon;
+
+impl Weapon for RapidFireCannon {
+    fn name(&self) -> &'static str {
+        "Rapid-Fire Cannon"
+    }
+
+    fn cooldown(&self) -> f64 {
+        0.08
+    }
+
+    fn kind(&self) -> WeaponKind {
+        WeaponKind::RapidFireCannon
+    }
+
+    fn fire(&self, _: f64) -> Vec<Shot> {
+        vec![Shot {
+            angle: 0.0,
+            projectile: Projectile {
+                speed: 600.0,
+                ttl: 0.7,
+                diameter: 2.0,
+                piercing: false,
+            },
+        }]
+    }
+}
+
+/// Charges while the trigger is held and fires a bullet that grows with the charge.
+/// A fully charged shot passes through the asteroids it destroys.
+pub struct ChargedShot;
+
+/// How much wider a fully charged shot is than an uncharged one, in pixels.
+const CHARGED_SHOT_GROWTH: f64 = 9.0;
+
+impl Weapon for ChargedShot {
+    fn name(&self) -> &'static str {
+        "Charged Shot"
+    }
+
+    fn cooldown(&self) -> f64 {
+        0.5
+    }
+
+    fn kind(&self) -> WeaponKind {
+        WeaponKind::ChargedShot
+    }
+
+    fn max_charge(&self) -> f64 {
+        1.0
+    }
+
+    fn fire(&self, charge: f64) -> Vec<Shot> {
+        vec![Shot {
+            angle: 0.0,
+            projectile: Projectile {
+                speed: 400.0,
+                ttl: 1.2,
+                diameter: Projectile::STANDARD.diameter + CHARGED_SHOT_GROWTH * charge,
+                piercing: charge >= 1.0,
+            },
+        }]
+    }
+}
+
+/// Fires a continuous beam that burns through the first asteroid in its way.
+pub struct Laser;
+
+impl Weapon for Laser {
+    fn name(&self) -> &'static str {
+        "Laser"
+    }
+
+    fn cooldown(&self) -> f64 {
+        0.0
+    }
+
+    fn kind(&self) -> WeaponKind {
+        WeaponKind::Laser
+    }
+
+    fn beam_range(&self) -> Option<f64> {
+        Some(400.0)
+    }
+
+    fn fire(&self, _: f64) -> Vec<Shot> {
+        Vec::new()
+    }
+}
+
+/// The weapons the ship's slots are filled with, in the order they are switched through.
+pub fn arsenal() -> Vec<Box<dyn Weapon>> {
+    vec![
+        Box::new(Blaster),
+        Box::new(SpreadGun),
+        Box::new(RapidFireCannon),
+        Box::new(ChargedShot),
+        Box::new(Laser),
+    ]
+}
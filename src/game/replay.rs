@@ -0,0 +1,446 @@
+//! Recording and playback of games.
+//!
+//! Because the simulation is deterministic for a given seed, a game can be
+//! reproduced exactly from the seed and the player's inputs. A `Replay` stores
+//! only the ticks on which the held `Actions` changed, which keeps replay files small.
+//!
+//! Replays are saved as plain text:
+//!
+//! ```text
+//! rust-belt-replay 1
+//! version 1.3.0
+//! simulation 1
+//! seed 1234
+//! mode Endless
+//! difficulty Normal
+//! lives 3
+//! scores 20 50 100
+//! extra-life 10000
+//! length 5321
+//! 0 0
+//! 120 8
+//! 161 a
+//! ```
+//!
+//! Every line after the header is a tick followed by the hexadecimal encoding of
+//! the actions that are held from that tick onward.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::models::player::Actions;
+use super::state::{Difficulty, Mode, Rules, ScoreTable, SIMULATION_VERSION};
+
+/// The most replays kept. Saving a new replay deletes the oldest ones beyond this.
+const MAX_REPLAYS: usize = 50;
+
+/// File extension used for replay files.
+const REPLAY_EXTENSION: &str = "replay";
+
+/// Identifies a replay file and the version of the file format.
+const MAGIC: &str = "rust-belt-replay 1";
+
+/// The version of the game a replay is recorded with.
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A recorded game.
+pub struct Replay {
+    /// The game version the replay was recorded with, shown to the player.
+    version: String,
+
+    /// The version of the simulation the replay was recorded with. Replays from
+    /// other simulations do not play back the same way.
+    simulation: u32,
+    seed: u64,
+    rules: Rules,
+
+    /// The ticks at which the held actions changed, in increasing order.
+    changes: Vec<(u64, Actions)>,
+
+    /// The number of ticks the game lasted.
+    length: u64,
+}
+
+impl Replay {
+    /// Starts a new, empty recording of a game using `seed` and `rules`.
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        Replay {
+            version: GAME_VERSION.to_string(),
+            simulation: SIMULATION_VERSION,
+            seed,
+            rules,
+            changes: Vec::new(),
+            length: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Whether the replay was recorded with this version of the simulation.
+    pub fn is_compatible(&self) -> bool {
+        self.simulation == SIMULATION_VERSION
+    }
+
+    /// Records the actions held during `tick`. Ticks must be recorded in order.
+    pub fn record(&mut self, tick: u64, actions: Actions) {
+        if self.changes.last().map(|&(_, last)| last) != Some(actions) {
+            self.changes.push((tick, actions));
+        }
+        self.length = tick + 1;
+    }
+
+    /// Writes the replay to `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut contents = format!(
+            "{MAGIC}\nversion {}\nsimulation {}\nseed {}\nmode {}\ndifficulty {}\nlives {}\nscores {} {} {}\nextra-life {}\nlength {}\n",
+            self.version,
+            self.simulation,
+            self.seed,
+            self.rules.mode.name(),
+            self.rules.difficulty.name(),
+            self.rules.lives,
+            self.rules.scores.large,
+            self.rules.scores.medium,
+            self.rules.scores.small,
+            self.rules.extra_life,
+            self.length
+        );
+        for (tick, actions) in &self.changes {
+            contents.push_str(&format!("{tick} {:x}\n", encode(*actions)));
+        }
+        fs::write(path, contents)
+    }
+
+    /// The directory replays are saved to and loaded from, if the platform has a
+    /// data directory.
+    fn dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rust-belt").join("replays"))
+    }
+
+    /// Saves the replay into the replay directory under a new, unique name, then
+    /// deletes the oldest replays so that at most `MAX_REPLAYS` are kept.
+    pub fn save_new(&self) -> io::Result<PathBuf> {
+        let dir = Replay::dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        fs::create_dir_all(&dir)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("{timestamp}-{}.{REPLAY_EXTENSION}", self.seed));
+        self.save(&path)?;
+
+        for old in Replay::list().into_iter().skip(MAX_REPLAYS) {
+            if let Err(error) = fs::remove_file(&old) {
+                eprintln!("Failed to delete old replay '{}': {error}", old.display());
+            }
+        }
+        Ok(path)
+    }
+
+    /// Reads a replay from `path`.
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        if lines.next() != Some(MAGIC) {
+            return Err(invalid_data("not a replay file"));
+        }
+        let version = header(lines.next(), "version")?.to_string();
+        let simulation = header(lines.next(), "simulation")?
+            .parse()
+            .map_err(|_| invalid_data("invalid simulation version"))?;
+        let seed = header(lines.next(), "seed")?
+            .parse()
+            .map_err(|_| invalid_data("invalid seed"))?;
+        let rules = Rules {
+            mode: Mode::from_name(header(lines.next(), "mode")?)
+                .ok_or_else(|| invalid_data("invalid mode"))?,
+            difficulty: Difficulty::from_name(header(lines.next(), "difficulty")?)
+                .ok_or_else(|| invalid_data("invalid difficulty"))?,
+            lives: header(lines.next(), "lives")?
+                .parse()
+                .map_err(|_| invalid_data("invalid lives"))?,
+            scores: parse_scores(header(lines.next(), "scores")?)
+                .ok_or_else(|| invalid_data("invalid scores"))?,
+            extra_life: header(lines.next(), "extra-life")?
+                .parse()
+                .ok()
+                .filter(|&score| score > 0)
+                .ok_or_else(|| invalid_data("invalid extra life score"))?,
+        };
+        let length = header(lines.next(), "length")?
+            .parse()
+            .map_err(|_| invalid_data("invalid length"))?;
+
+        let mut changes: Vec<(u64, Actions)> = Vec::new();
+        for line in lines {
+            let (tick, bits) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid_data("invalid input line"))?;
+            let tick = tick
+                .parse()
+                .map_err(|_| invalid_data("invalid input tick"))?;
+            let bits =
+                u8::from_str_radix(bits, 16).map_err(|_| invalid_data("invalid input actions"))?;
+            if changes.last().is_some_and(|&(last, _)| last >= tick) {
+                return Err(invalid_data("input ticks are out of order"));
+            }
+            changes.push((tick, decode(bits)));
+        }
+
+        Ok(Replay {
+            version,
+            simulation,
+            seed,
+            rules,
+            changes,
+            length,
+        })
+    }
+
+    /// Lists the paths of all saved replays, newest first.
+    pub fn list() -> Vec<PathBuf> {
+        let Some(dir) = Replay::dir() else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|extension| extension == REPLAY_EXTENSION)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // File names start with a timestamp, so sorting them sorts by age.
+        paths.sort();
+        paths.reverse();
+        paths
+    }
+}
+
+/// Feeds the actions stored in a `Replay` back tick by tick.
+pub struct Playback {
+    replay: Replay,
+
+    /// Index of the next change in `replay` that has not been applied yet.
+    next_change: usize,
+    actions: Actions,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            replay,
+            next_change: 0,
+            actions: Actions::default(),
+        }
+    }
+
+    /// Returns the actions that were held during `tick`. Ticks must be requested in order.
+    pub fn actions(&mut self, tick: u64) -> Actions {
+        while let Some(&(change_tick, actions)) = self.replay.changes.get(self.next_change) {
+            if change_tick > tick {
+                break;
+            }
+            self.actions = actions;
+            self.next_change += 1;
+        }
+        self.actions
+    }
+
+    /// Whether every recorded tick has been played back.
+    pub fn is_finished(&self, tick: u64) -> bool {
+        tick >= self.replay.length
+    }
+}
+
+/// Reads the value of a `name value` header line.
+fn header<'a>(line: Option<&'a str>, name: &str) -> io::Result<&'a str> {
+    line.and_then(|line| line.strip_prefix(name))
+        .and_then(|rest| rest.strip_prefix(' '))
+        .ok_or_else(|| invalid_data(&format!("missing {name}")))
+}
+
+/// Reads the points for large, medium and small asteroids, separated by spaces.
+fn parse_scores(scores: &str) -> Option<ScoreTable> {
+    let mut points = scores.split(' ').map(|points| points.parse().ok());
+    let table = ScoreTable {
+        large: points.next()??,
+        medium: points.next()??,
+        small: points.next()??,
+    };
+    points.next().is_none().then_some(table)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Packs actions into one bit per action.
+fn encode(actions: Actions) -> u8 {
+    [
+        actions.rotate_cw,
+        actions.rotate_ccw,
+        actions.fire_boosters,
+        actions.fire_rev_boosters,
+        actions.is_shooting,
+        actions.hyperspace,
+        actions.switch_weapon,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |bits, (index, &held)| bits | (u8::from(held) << index))
+}
+
+/// Unpacks actions packed by `encode`.
+fn decode(bits: u8) -> Actions {
+    let held = |index: u8| bits & (1 << index) != 0;
+    Actions {
+        rotate_cw: held(0),
+        rotate_ccw: held(1),
+        fire_boosters: held(2),
+        fire_rev_boosters: held(3),
+        is_shooting: held(4),
+        hyperspace: held(5),
+        switch_weapon: held(6),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{decode, encode, Playback, Replay, MAGIC};
+    use crate::game::models::player::Actions;
+    use crate::game::state::{Difficulty, Mode, Rules, ScoreTable};
+
+    /// A file in the temporary directory that is deleted again when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let file_name = format!("rust-belt-{}-{name}.replay", std::process::id());
+            TempFile(std::env::temp_dir().join(file_name))
+        }
+
+        fn with_contents(name: &str, contents: &str) -> Self {
+            let file = TempFile::new(name);
+            fs::write(&file.0, contents).unwrap();
+            file
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    const SHOOTING: Actions = Actions {
+        rotate_cw: false,
+        rotate_ccw: false,
+        fire_boosters: false,
+        fire_rev_boosters: false,
+        is_shooting: true,
+        hyperspace: false,
+        switch_weapon: false,
+    };
+
+    #[test]
+    fn saved_replays_load_the_same() {
+        let rules = Rules {
+            mode: Mode::Waves,
+            difficulty: Difficulty::Hard,
+            lives: 5,
+            scores: ScoreTable {
+                large: 25,
+                medium: 60,
+                small: 150,
+            },
+            extra_life: 7500,
+        };
+        let mut replay = Replay::new(1234, rules);
+        replay.record(0, Actions::default());
+        replay.record(1, SHOOTING);
+        replay.record(2, SHOOTING);
+        replay.record(3, Actions::default());
+
+        let file = TempFile::new("round-trip");
+        replay.save(&file.0).unwrap();
+        let loaded = Replay::load(&file.0).unwrap();
+        assert_eq!(loaded.version(), replay.version());
+        assert!(loaded.is_compatible());
+        assert_eq!(loaded.seed(), 1234);
+        assert_eq!(loaded.rules(), rules);
+        assert_eq!(loaded.length(), 4);
+        assert_eq!(loaded.changes, replay.changes);
+    }
+
+    #[test]
+    fn encodes_every_action() {
+        for bits in 0..0x80 {
+            assert_eq!(encode(decode(bits)), bits);
+        }
+        let all = decode(0x7f);
+        assert!(all.rotate_cw && all.rotate_ccw && all.fire_boosters && all.fire_rev_boosters);
+        assert!(all.is_shooting && all.hyperspace && all.switch_weapon);
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let header = format!(
+            "{MAGIC}\nversion 1.3.0\nsimulation 1\nseed 1\nmode Endless\ndifficulty Normal\nlives 3\nscores 20 50 100\nextra-life 10000\nlength 10\n"
+        );
+        let valid = TempFile::with_contents("valid", &format!("{header}0 0\n5 10\n"));
+        assert!(Replay::load(&valid.0).is_ok());
+
+        let out_of_order = TempFile::with_contents("out-of-order", &format!("{header}5 10\n0 0\n"));
+        assert!(Replay::load(&out_of_order.0).is_err());
+
+        let missing_seed = header.replace("seed 1\n", "");
+        let missing_header = TempFile::with_contents("missing-header", &missing_seed);
+        let error = Replay::load(&missing_header.0).err().unwrap();
+        assert_eq!(error.to_string(), "missing seed");
+
+        let not_a_replay = TempFile::with_contents("not-a-replay", "hello\n");
+        assert!(Replay::load(&not_a_replay.0).is_err());
+    }
+
+    #[test]
+    fn plays_back_the_recorded_actions() {
+        let mut replay = Replay::new(1, Rules::new());
+        replay.record(0, Actions::default());
+        replay.record(1, SHOOTING);
+        replay.record(2, SHOOTING);
+        replay.record(3, Actions::default());
+
+        let mut playback = Playback::new(replay);
+        let played: Vec<_> = (0..4).map(|tick| playback.actions(tick)).collect();
+        assert_eq!(
+            played,
+            [Actions::default(), SHOOTING, SHOOTING, Actions::default()]
+        );
+        assert!(!playback.is_finished(3));
+        assert!(playback.is_finished(4));
+    }
+}
@@ -0,0 +1,128 @@
+//! Defines a uniform grid used to quickly find objects that might be colliding.
+//!
+//! The grid exactly tiles the playfield and its cells wrap around at the edges
+//! like the playfield does, so objects near opposite edges share cells. It is
+//! only a broadphase: objects that share a cell still need to be checked with
+//! `Collidable::collides_with`.
+
+use std::ops::Range;
+
+use piston_window::Size;
+
+use super::models::Collidable;
+
+pub struct SpatialHash {
+    cell_width: f64,
+    cell_height: f64,
+    columns: usize,
+    rows: usize,
+
+    /// The indices of the objects overlapping each cell, stored row by row.
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialHash {
+    /// Creates an empty grid covering `bounds` with cells roughly `cell_size` wide and high.
+    pub fn new(bounds: Size, cell_size: f64) -> Self {
+        let columns = ((bounds.width / cell_size).floor() as usize).max(1);
+        let rows = ((bounds.height / cell_size).floor() as usize).max(1);
+        SpatialHash {
+            cell_width: bounds.width / columns as f64,
+            cell_height: bounds.height / rows as f64,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        }
+    }
+
+    /// Removes every object from the grid.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+    }
+
+    /// Adds the object with the given index to every cell its bounding circle overlaps.
+    pub fn insert<C: Collidable>(&mut self, index: usize, object: &C) {
+        for cell in self.covered_cells(object) {
+            self.cells[cell].push(index);
+        }
+    }
+
+    /// Returns the indices of all objects sharing a cell with `object`, in
+    /// increasing order and without duplicates.
+    pub fn query<C: Collidable>(&self, object: &C) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .covered_cells(object)
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// The cells overlapped by the bounding circle of `object`.
+    fn covered_cells<C: Collidable>(&self, object: &C) -> impl Iterator<Item = usize> {
+        let radius = object.radius();
+        let columns = wrapped_range(
+            object.x() - radius,
+            object.x() + radius,
+            self.cell_width,
+            self.columns,
+        );
+        let rows = wrapped_range(
+            object.y() - radius,
+            object.y() + radius,
+            self.cell_height,
+            self.rows,
+        );
+
+        let (num_columns, num_rows) = (self.columns, self.rows);
+        rows.flat_map(move |row| {
+            columns.clone().map(move |column| {
+                row.rem_euclid(num_rows as i64) as usize * num_columns
+                    + column.rem_euclid(num_columns as i64) as usize
+            })
+        })
+    }
+}
+
+/// The unwrapped cell indices spanned by `min..=max` along one axis, clamped so that
+/// no cell is visited twice when the span is wider than the whole grid.
+fn wrapped_range(min: f64, max: f64, cell_size: f64, num_cells: usize) -> Range<i64> {
+    let first = (min / cell_size).floor() as i64;
+    let last = (max / cell_size).floor() as i64;
+    first..(last + 1).min(first + num_cells as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wrapped_range, SpatialHash};
+    use crate::game::models::testing::{Shape, BOUNDS};
+
+    #[test]
+    fn finds_objects_across_edges() {
+        let mut grid = SpatialHash::new(BOUNDS, 100.0);
+        grid.insert(0, &Shape::circle(795.0, 300.0));
+        grid.insert(1, &Shape::circle(795.0, 595.0));
+        grid.insert(2, &Shape::circle(400.0, 300.0));
+
+        assert_eq!(grid.query(&Shape::circle(5.0, 300.0)), [0]);
+        assert_eq!(grid.query(&Shape::circle(5.0, 5.0)), [1]);
+    }
+
+    #[test]
+    fn visits_each_cell_once() {
+        let cells: Vec<_> = wrapped_range(-500.0, 500.0, 100.0, 8)
+            .map(|cell| cell.rem_euclid(8))
+            .collect();
+        assert_eq!(cells.len(), 8);
+        assert!((0..8).all(|cell| cells.contains(&cell)));
+
+        let mut grid = SpatialHash::new(BOUNDS, 100.0);
+        let mut huge = Shape::circle(400.0, 300.0);
+        huge.radius = 1000.0;
+        grid.insert(0, &huge);
+        assert!(grid.cells.iter().all(|cell| cell == &[0]));
+    }
+}
@@ -0,0 +1,983 @@
+//! Defines the headless game simulation.
+//!
+//! `GameState` owns every object that exists within a game and advances them
+//! with `step`. It does not know anything about windows, graphics or sound, so
+//! gameplay can be driven without opening a window. Anything the outside world
+//! might want to react to (such as playing a sound) is reported back as a
+//! `GameEvent`.
+
+use std::mem;
+
+use piston_window::{Size, UpdateArgs};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::models::asteroid::SizeClass;
+use super::models::powerup::{self, Kind};
+use super::models::saucer::{self, SaucerSize};
+use super::models::vector::Vector;
+use super::models::weapon::WeaponKind;
+use super::models::{asteroid, beam, bullet, player, popup, Collidable, Positioned, Updateable};
+use super::spatial_hash::SpatialHash;
+
+/// The length of a single simulation step in seconds.
+///
+/// The simulation is always advanced in steps of this size regardless of how
+/// often the window delivers updates, so that it behaves identically on every machine.
+pub const TIMESTEP: f64 = 1.0 / 120.0;
+
+/// The version of the simulation. A replay only plays back the same way with the
+/// simulation it was recorded with, so this must be bumped by every change that
+/// makes the same seed, rules and inputs play out differently.
+pub const SIMULATION_VERSION: u32 = 1;
+
+/// The approximate size of a broadphase cell, about the width of a large asteroid.
+const BROADPHASE_CELL_SIZE: f64 = 128.0;
+
+/// The most lives the player can have at once.
+const MAX_LIVES: u32 = 9;
+
+/// The numbers of lives a game can be started with.
+pub const STARTING_LIVES: [u32; 5] = [1, 2, 3, 4, 5];
+
+/// The least time in seconds between the player being destroyed and respawning.
+const RESPAWN_DELAY: f64 = 1.5;
+
+/// The player only respawns once no asteroid is within this distance of the center.
+const RESPAWN_CLEAR_RADIUS: f64 = 150.0;
+
+/// Seconds the player cannot be hit for after respawning.
+const RESPAWN_INVULNERABILITY: f64 = 3.0;
+
+/// Seconds the player has to destroy another asteroid before the multiplier drops.
+const COMBO_WINDOW: f64 = 2.0;
+
+/// The number of consecutive kills that raise the multiplier by one.
+const KILLS_PER_MULTIPLIER: u32 = 4;
+
+/// The highest the score multiplier can go.
+const MAX_MULTIPLIER: u32 = 8;
+
+/// The chance that a destroyed asteroid drops a power-up.
+const POWERUP_DROP_CHANCE: f64 = 0.1;
+
+/// The least and most seconds between saucers appearing.
+const SAUCER_INTERVAL_MIN: f64 = 15.0;
+const SAUCER_INTERVAL_MAX: f64 = 25.0;
+
+/// Saucers are more likely to be small the higher the score, up to this chance
+/// once the score reaches `SMALL_SAUCER_SCORE`.
+const MAX_SMALL_SAUCER_CHANCE: f64 = 0.8;
+const SMALL_SAUCER_SCORE: f64 = 40000.0;
+
+/// The chance that a hyperspace jump fails and destroys the ship.
+const HYPERSPACE_FAILURE_CHANCE: f64 = 0.1;
+
+/// Seconds between clearing a sector and the next wave arriving.
+const SECTOR_INTERLUDE: f64 = 3.0;
+
+/// The most asteroids in a single wave.
+const MAX_WAVE_SIZE: u32 = 16;
+
+/// Notable things that happened during a single `step` of the simulation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The player fired the given weapon.
+    WeaponShoot(WeaponKind),
+
+    /// A bullet destroyed an asteroid.
+    AsteroidExplosion,
+
+    /// The player's shield absorbed an asteroid impact.
+    ShieldImpact,
+
+    /// The player jumped through hyperspace.
+    Hyperspace,
+
+    /// A saucer fired a bullet.
+    SaucerShoot,
+
+    /// A bullet destroyed a saucer.
+    SaucerDestroyed,
+
+    /// An asteroid hit the player, who lost a life.
+    PlayerDestroyed,
+
+    /// The score reached a threshold and the player gained a life.
+    ExtraLife,
+
+    /// The player picked up a power-up.
+    PowerUpCollected,
+
+    /// The player destroyed every asteroid of a wave.
+    SectorCleared,
+
+    /// The player lost their last life and the game is over.
+    GameOver,
+}
+
+/// How asteroids are brought into the game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Asteroids keep arriving one at a time, more and more often.
+    Endless,
+
+    /// Asteroids arrive in waves, one per sector. Each wave is larger and faster than
+    /// the one before, and only arrives once the previous wave is destroyed.
+    Waves,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Endless, Mode::Waves];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Endless => "Endless",
+            Mode::Waves => "Waves",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Mode::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How quickly asteroids appear, and how many arrive in each wave.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Every difficulty, from easiest to hardest.
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The seconds between asteroid spawns at the start of a game, and the
+    /// shortest that time becomes as the game goes on.
+    fn spawn_intervals(self) -> (f64, f64) {
+        match self {
+            Difficulty::Easy => (5.0, 1.0),
+            Difficulty::Normal => (4.0, 0.5),
+            Difficulty::Hard => (3.0, 0.3),
+        }
+    }
+
+    /// The asteroids that arrive in `sector`, counting from 1.
+    fn wave(self, sector: u32) -> Wave {
+        let first_wave_size = match self {
+            Difficulty::Easy => 2,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 4,
+        };
+        let escalation = f64::from(sector - 1);
+        Wave {
+            count: (first_wave_size + sector - 1).min(MAX_WAVE_SIZE),
+            size: (1.0 + 0.05 * escalation).min(1.5),
+            speed: (1.0 + 0.1 * escalation).min(2.0),
+        }
+    }
+}
+
+/// A group of asteroids that arrive together.
+struct Wave {
+    count: u32,
+
+    /// How large and how fast the asteroids are compared to those of the endless mode.
+    size: f64,
+    speed: f64,
+}
+
+impl Wave {
+    /// Creates the asteroids of the wave just off-screen.
+    fn spawn(&self, bounds: Size, rng: &mut StdRng) -> Vec<asteroid::Asteroid> {
+        (0..self.count)
+            .map(|_| asteroid::Asteroid::scaled(bounds, self.size, self.speed, rng))
+            .collect()
+    }
+}
+
+/// Decides when new asteroids are brought into the game, according to the `Mode`.
+enum Spawner {
+    Endless {
+        /// Seconds until the next asteroid is spawned.
+        timer: f64,
+        timer_max: f64,
+        timer_min: f64,
+    },
+    Waves {
+        difficulty: Difficulty,
+
+        /// The sector whose wave is being fought, or was just cleared. 0 before the first wave.
+        sector: u32,
+
+        /// Seconds until the next wave arrives. Present only between waves.
+        interlude: Option<f64>,
+    },
+}
+
+impl Spawner {
+    fn new(rules: Rules) -> Self {
+        match rules.mode {
+            Mode::Endless => {
+                let (timer_max, timer_min) = rules.difficulty.spawn_intervals();
+                Spawner::Endless {
+                    timer: 0.1,
+                    timer_max,
+                    timer_min,
+                }
+            }
+            Mode::Waves => Spawner::Waves {
+                difficulty: rules.difficulty,
+                sector: 0,
+                interlude: Some(0.1),
+            },
+        }
+    }
+}
+
+/// Options that change how a game plays out. Like the seed, they must be the
+/// same for a game to be reproduced.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub mode: Mode,
+    pub difficulty: Difficulty,
+
+    /// The lives the player starts with.
+    pub lives: u32,
+    pub scores: ScoreTable,
+
+    /// An extra life is awarded every time the score passes a multiple of this.
+    pub extra_life: i64,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Rules {
+            mode: Mode::Endless,
+            difficulty: Difficulty::Normal,
+            lives: 3,
+            scores: ScoreTable::new(),
+            extra_life: 10000,
+        }
+    }
+}
+
+/// The points awarded for destroying an asteroid of each size. Like in the arcade
+/// original, smaller asteroids are harder to hit and worth more.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScoreTable {
+    pub large: i64,
+    pub medium: i64,
+    pub small: i64,
+}
+
+impl ScoreTable {
+    pub fn new() -> Self {
+        ScoreTable {
+            large: 20,
+            medium: 50,
+            small: 100,
+        }
+    }
+
+    pub fn points(&self, size: SizeClass) -> i64 {
+        match size {
+            SizeClass::Large => self.large,
+            SizeClass::Medium => self.medium,
+            SizeClass::Small => self.small,
+        }
+    }
+}
+
+/// A circular area of the playfield, used to check it for asteroids.
+struct Area {
+    pos: Vector,
+    radius: f64,
+}
+
+impl Positioned for Area {
+    fn pos(&self) -> Vector {
+        self.pos
+    }
+}
+
+impl Collidable for Area {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+/// A score multiplier built up by destroying asteroids in quick succession.
+struct Combo {
+    multiplier: u32,
+
+    /// Kills made towards raising the multiplier again.
+    kills: u32,
+
+    /// Seconds left to make the next kill before the multiplier drops one level.
+    timer: f64,
+}
+
+impl Combo {
+    fn new() -> Self {
+        Combo {
+            multiplier: 1,
+            kills: 0,
+            timer: 0.0,
+        }
+    }
+
+    /// Counts a kill towards the combo and restarts its timer.
+    fn kill(&mut self) {
+        self.timer = COMBO_WINDOW;
+        self.kills += 1;
+        if self.kills >= KILLS_PER_MULTIPLIER && self.multiplier < MAX_MULTIPLIER {
+            self.multiplier += 1;
+            self.kills = 0;
+        }
+    }
+
+    /// Lets the combo decay one level every `COMBO_WINDOW` seconds without a kill.
+    fn update(&mut self, dt: f64) {
+        if self.timer <= 0.0 {
+            return;
+        }
+        self.timer -= dt;
+        if self.timer <= 0.0 {
+            self.kills = 0;
+            if self.multiplier > 1 {
+                self.multiplier -= 1;
+                self.timer = COMBO_WINDOW;
+            }
+        }
+    }
+}
+
+/// Stores the simulation state and all objects that exist.
+pub struct GameState {
+    player: player::Player,
+
+    /// The bullets that are currently live in the window.
+    /// Bullets are removed when their TTL expires.
+    bullets: Vec<bullet::Bullet>,
+
+    /// The laser beam the player is firing, if any.
+    beam: Option<beam::Beam>,
+    asteroids: Vec<asteroid::Asteroid>,
+    saucers: Vec<saucer::Saucer>,
+
+    /// The bullets fired by saucers, which only hit the player.
+    saucer_bullets: Vec<bullet::Bullet>,
+
+    /// Seconds until the next saucer appears.
+    saucer_timer: f64,
+    powerups: Vec<powerup::PowerUp>,
+    score: i64,
+    scores: ScoreTable,
+    combo: Combo,
+
+    /// The points scored recently, shown where they were scored until they fade out.
+    popups: Vec<popup::ScorePopup>,
+    lives: u32,
+
+    /// The score at which the next extra life is awarded, and how much more the
+    /// one after it takes.
+    next_extra_life: i64,
+    extra_life: i64,
+
+    /// Seconds left until the player may respawn. Present only while the player is destroyed.
+    respawn_timer: Option<f64>,
+
+    /// The size of the playfield, which wraps around at its edges.
+    bounds: Size,
+    spawner: Spawner,
+
+    /// A flag indicating if the player has lost.
+    /// This should not be set if the player simply quits.
+    game_over: bool,
+
+    /// The seed `rng` was created from, kept so a game can be reproduced.
+    seed: u64,
+
+    /// The single source of randomness for all gameplay.
+    rng: StdRng,
+
+    /// Finds the asteroids near a bullet or the player, so collisions are only
+    /// checked against those. Rebuilt every step.
+    broadphase: SpatialHash,
+}
+
+impl GameState {
+    /// Creates a new game. Two games created with the same `seed` and `rules`
+    /// and fed the same inputs will play out identically.
+    pub fn new(bounds: Size, seed: u64, rules: Rules) -> Self {
+        GameState {
+            player: player::Player::new(bounds),
+            bullets: Vec::new(),
+            beam: None,
+            asteroids: Vec::new(),
+            saucers: Vec::new(),
+            saucer_bullets: Vec::new(),
+            saucer_timer: SAUCER_INTERVAL_MAX,
+            powerups: Vec::new(),
+            score: 0,
+            scores: rules.scores,
+            combo: Combo::new(),
+            popups: Vec::new(),
+            lives: rules.lives.clamp(1, MAX_LIVES),
+            next_extra_life: rules.extra_life,
+            extra_life: rules.extra_life,
+            respawn_timer: None,
+            bounds,
+            spawner: Spawner::new(rules),
+            game_over: false,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            broadphase: SpatialHash::new(bounds, BROADPHASE_CELL_SIZE),
+        }
+    }
+
+    /// The player's ship, unless it was destroyed and has not respawned yet.
+    pub fn player(&self) -> Option<&player::Player> {
+        match self.respawn_timer {
+            Some(_) => None,
+            None => Some(&self.player),
+        }
+    }
+
+    pub fn bullets(&self) -> &[bullet::Bullet] {
+        &self.bullets
+    }
+
+    pub fn beam(&self) -> Option<&beam::Beam> {
+        self.beam.as_ref()
+    }
+
+    pub fn asteroids(&self) -> &[asteroid::Asteroid] {
+        &self.asteroids
+    }
+
+    pub fn bounds(&self) -> Size {
+        self.bounds
+    }
+
+    pub fn saucers(&self) -> &[saucer::Saucer] {
+        &self.saucers
+    }
+
+    pub fn saucer_bullets(&self) -> &[bullet::Bullet] {
+        &self.saucer_bullets
+    }
+
+    pub fn powerups(&self) -> &[powerup::PowerUp] {
+        &self.powerups
+    }
+
+    pub fn popups(&self) -> &[popup::ScorePopup] {
+        &self.popups
+    }
+
+    /// The number every score is currently multiplied by.
+    pub fn multiplier(&self) -> u32 {
+        self.combo.multiplier
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    /// The sector that was just cleared, while waiting for the next wave to arrive.
+    pub fn cleared_sector(&self) -> Option<u32> {
+        match self.spawner {
+            Spawner::Waves {
+                sector,
+                interlude: Some(_),
+                ..
+            } if sector > 0 => Some(sector),
+            _ => None,
+        }
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Scores the asteroid at `index` destroyed by `cause`, possibly leaving a power-up
+    /// behind, and adds the pieces it splits into to `fragments`. The caller is left to
+    /// remove the asteroid itself.
+    fn destroy_asteroid<P: Positioned>(
+        &mut self,
+        index: usize,
+        cause: &P,
+        fragments: &mut Vec<asteroid::Asteroid>,
+        events: &mut Vec<GameEvent>,
+    ) {
+        let points = self.scores.points(self.asteroids[index].size_class())
+            * i64::from(self.combo.multiplier);
+        self.combo.kill();
+        self.popups.push(popup::ScorePopup::new(
+            self.asteroids[index].pos(),
+            points,
+            self.bounds,
+        ));
+        if self.rng.gen::<f64>() < POWERUP_DROP_CHANCE {
+            self.powerups.push(powerup::PowerUp::new(
+                self.asteroids[index].pos(),
+                self.bounds,
+                &mut self.rng,
+            ));
+        }
+        if self.asteroids[index].can_split() {
+            fragments.extend(self.asteroids[index].split(cause, self.bounds));
+        }
+        self.score += points;
+        events.push(GameEvent::AsteroidExplosion);
+    }
+
+    /// Advances the simulation by `dt` seconds using the given player inputs.
+    /// Returns the events that occurred during the step, in order. Once the game
+    /// is over nothing changes any more.
+    pub fn step(&mut self, dt: f64, inputs: &player::Actions) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.game_over {
+            return events;
+        }
+        let args = UpdateArgs { dt };
+        let mut hyperspace_failed = false;
+
+        match self.respawn_timer {
+            Some(timer) => {
+                self.respawn_timer = Some(timer - dt);
+                if timer - dt <= 0.0 && self.respawn_area_is_clear() {
+                    self.respawn_timer = None;
+                    self.player = player::Player::new(self.bounds);
+                    self.player.make_invulnerable(RESPAWN_INVULNERABILITY);
+                }
+            }
+            None => {
+                self.player.actions = *inputs;
+                self.player.update(args, self.bounds);
+                if self.player.hyperspace_ready() {
+                    events.push(GameEvent::Hyperspace);
+                    if self.rng.gen::<f64>() < HYPERSPACE_FAILURE_CHANCE {
+                        hyperspace_failed = true;
+                    } else {
+                        let (width, height) = (self.bounds.width, self.bounds.height);
+                        let destination = Vector::new_rand(&mut self.rng, 0.0, 0.0, width, height);
+                        self.player.hyperspace(destination);
+                    }
+                }
+                let fired = self.player.shoot();
+                if !fired.is_empty() {
+                    events.push(GameEvent::WeaponShoot(self.player.weapon().kind()));
+                    self.bullets.extend(fired);
+                }
+
+                let player = &mut self.player;
+                self.powerups.retain(|powerup| {
+                    if !powerup.collides_with(player, self.bounds) {
+                        return true;
+                    }
+                    events.push(GameEvent::PowerUpCollected);
+                    match powerup.kind() {
+                        Kind::ExtraLife if self.lives < MAX_LIVES => {
+                            self.lives += 1;
+                            events.push(GameEvent::ExtraLife);
+                        }
+                        kind => player.power_up(kind),
+                    }
+                    false
+                });
+            }
+        }
+
+        // Update bullet position and remove those that time out.
+        for bullet in &mut self.bullets {
+            bullet.update(args, self.bounds);
+        }
+        self.bullets.retain(|bullet| bullet.ttl() > 0.0);
+
+        for asteroid in &mut self.asteroids {
+            asteroid.update(args, self.bounds);
+        }
+
+        let target = self.player().map(|player| player.pos);
+        for saucer in &mut self.saucers {
+            saucer.think(dt, target, self.bounds);
+            saucer.update(args, self.bounds);
+            if let Some(bullet) = saucer.shoot(target, self.bounds, &mut self.rng) {
+                events.push(GameEvent::SaucerShoot);
+                self.saucer_bullets.push(bullet);
+            }
+        }
+        self.saucers.retain(|saucer| !saucer.has_left(self.bounds));
+        for bullet in &mut self.saucer_bullets {
+            bullet.update(args, self.bounds);
+        }
+        self.saucer_bullets.retain(|bullet| bullet.ttl() > 0.0);
+
+        for powerup in &mut self.powerups {
+            powerup.update(args, self.bounds);
+        }
+        self.powerups.retain(|powerup| powerup.ttl() > 0.0);
+
+        for popup in &mut self.popups {
+            popup.update(args, self.bounds);
+        }
+        self.popups.retain(|popup| popup.ttl() > 0.0);
+
+        self.broadphase.clear();
+        for (index, asteroid) in self.asteroids.iter().enumerate() {
+            self.broadphase.insert(index, asteroid);
+        }
+
+        // Asteroids are only removed and split after every bullet has been checked,
+        // so that the indices stored in the broadphase stay valid.
+        let mut destroyed = vec![false; self.asteroids.len()];
+        let mut fragments = Vec::new();
+        self.combo.update(dt);
+
+        // The laser beam burns the first asteroid in its way for as long as it is fired.
+        let was_firing = self.beam.is_some();
+        self.beam = None;
+        if let Some(range) = self.player().and_then(|player| player.beam_range()) {
+            if !was_firing {
+                events.push(GameEvent::WeaponShoot(self.player.weapon().kind()));
+            }
+            let (start, rot) = (self.player.nose(), self.player.rot);
+            let direction = Vector {
+                x: rot.cos(),
+                y: rot.sin(),
+            };
+            let reach = Area {
+                pos: start + direction * (range / 2.0),
+                radius: range / 2.0,
+            };
+            let asteroids = &self.asteroids;
+            let candidates = self
+                .broadphase
+                .query(&reach)
+                .into_iter()
+                .map(|index| (index, &asteroids[index]));
+            let (beam, hit) = beam::Beam::cast(start, rot, range, candidates, self.bounds);
+            if let Some(index) = hit {
+                if self.asteroids[index].burn(dt) {
+                    let tip = Area {
+                        pos: beam.end(),
+                        radius: 0.0,
+                    };
+                    self.destroy_asteroid(index, &tip, &mut fragments, &mut events);
+                    destroyed[index] = true;
+                }
+            }
+            self.beam = Some(beam);
+        }
+
+        let mut bullets = mem::take(&mut self.bullets);
+        bullets.retain(|bullet| {
+            let saucer_hit = self
+                .saucers
+                .iter()
+                .position(|saucer| saucer.collides_with(bullet, self.bounds));
+            if let Some(index) = saucer_hit {
+                let saucer = self.saucers.remove(index);
+                let points = saucer.size().points() * i64::from(self.combo.multiplier);
+                self.combo.kill();
+                self.popups
+                    .push(popup::ScorePopup::new(saucer.pos(), points, self.bounds));
+                self.score += points;
+                events.push(GameEvent::SaucerDestroyed);
+                return bullet.is_piercing();
+            }
+
+            // Remove the first asteroid that collides with a bullet, if any.
+            let hit = self.broadphase.query(bullet).into_iter().find(|&index| {
+                !destroyed[index] && self.asteroids[index].collides_with(bullet, self.bounds)
+            });
+            if let Some(index) = hit {
+                self.destroy_asteroid(index, bullet, &mut fragments, &mut events);
+                destroyed[index] = true;
+                return bullet.is_piercing();
+            }
+            true
+        });
+        self.bullets = bullets;
+
+        while self.score >= self.next_extra_life {
+            self.next_extra_life += self.extra_life;
+            if self.lives < MAX_LIVES {
+                self.lives += 1;
+                events.push(GameEvent::ExtraLife);
+            }
+        }
+
+        // If the player hits an asteroid their shield absorbs the impact for as long as it
+        // has energy. If they hit a saucer or a saucer's bullet, or a hyperspace jump
+        // fails, they lose a life. Once out of lives the game is over.
+        let player = &self.player;
+        let vulnerable = self.respawn_timer.is_none()
+            && !player.is_invulnerable()
+            && !player.effects().is_active(Kind::Shield);
+        let impact = if vulnerable && !player.is_bouncing() {
+            self.broadphase
+                .query(player)
+                .into_iter()
+                .filter(|&index| !destroyed[index])
+                .map(|index| &self.asteroids[index])
+                .chain(&fragments)
+                .find(|asteroid| asteroid.collides_with(player, self.bounds))
+                .map(|asteroid| (asteroid.pos(), asteroid.vel(), asteroid.radius()))
+        } else {
+            None
+        };
+        let mut player_hit = hyperspace_failed;
+        if let Some((position, velocity, radius)) = impact {
+            if self
+                .player
+                .absorb_impact(position, velocity, radius, self.bounds)
+            {
+                events.push(GameEvent::ShieldImpact);
+            } else {
+                player_hit = true;
+            }
+        }
+        if vulnerable {
+            let player = &self.player;
+            // Saucers and their bullets are destroyed along with the player.
+            let (saucers, saucer_bullets) = (self.saucers.len(), self.saucer_bullets.len());
+            self.saucers
+                .retain(|saucer| !saucer.collides_with(player, self.bounds));
+            self.saucer_bullets
+                .retain(|bullet| !bullet.collides_with(player, self.bounds));
+            player_hit |=
+                self.saucers.len() < saucers || self.saucer_bullets.len() < saucer_bullets;
+        }
+        if player_hit {
+            self.lives -= 1;
+            events.push(GameEvent::PlayerDestroyed);
+            self.combo = Combo::new();
+            if self.lives == 0 {
+                self.game_over = true;
+                events.push(GameEvent::GameOver);
+            } else {
+                self.respawn_timer = Some(RESPAWN_DELAY);
+            }
+        }
+
+        let mut index = 0;
+        self.asteroids.retain(|_| {
+            index += 1;
+            !destroyed[index - 1]
+        });
+        self.asteroids.extend(fragments);
+
+        match self.spawner {
+            Spawner::Endless {
+                ref mut timer,
+                ref mut timer_max,
+                timer_min,
+            } => {
+                // Countdown a timer which controls when the next asteroid is spawned.
+                *timer -= dt;
+                if *timer < 0.0 {
+                    self.asteroids
+                        .push(asteroid::Asteroid::new(self.bounds, &mut self.rng));
+
+                    // After spawning an asteroid, reduce the timer to spawn the next
+                    // so that asteroids gradually being spawning faster and faster, up to a
+                    // certain limit.
+                    if *timer_max > timer_min {
+                        *timer_max -= 0.075;
+                    }
+                    *timer = *timer_max;
+                }
+            }
+            Spawner::Waves {
+                difficulty,
+                ref mut sector,
+                ref mut interlude,
+            } => match *interlude {
+                Some(time) if time - dt <= 0.0 => {
+                    *interlude = None;
+                    *sector += 1;
+                    let wave = difficulty.wave(*sector);
+                    self.asteroids
+                        .extend(wave.spawn(self.bounds, &mut self.rng));
+                }
+                Some(time) => *interlude = Some(time - dt),
+                None if self.asteroids.is_empty() => {
+                    events.push(GameEvent::SectorCleared);
+                    *interlude = Some(SECTOR_INTERLUDE);
+                }
+                None => {}
+            },
+        }
+
+        // Countdown a timer which controls when the next saucer appears. Only one
+        // saucer is around at a time, so the timer waits until the last one is gone.
+        if self.saucers.is_empty() {
+            self.saucer_timer -= dt;
+        }
+        if self.saucer_timer < 0.0 {
+            let small_chance =
+                (self.score as f64 / SMALL_SAUCER_SCORE).min(MAX_SMALL_SAUCER_CHANCE);
+            let size = if self.rng.gen::<f64>() < small_chance {
+                SaucerSize::Small
+            } else {
+                SaucerSize::Large
+            };
+            self.saucers
+                .push(saucer::Saucer::new(size, self.bounds, &mut self.rng));
+            self.saucer_timer = self.rng.gen_range(SAUCER_INTERVAL_MIN..SAUCER_INTERVAL_MAX);
+        }
+
+        events
+    }
+
+    /// Whether the center of the playfield, where the player respawns, is free of asteroids.
+    fn respawn_area_is_clear(&self) -> bool {
+        let area = Area {
+            pos: Vector::from(self.bounds) / 2.0,
+            radius: RESPAWN_CLEAR_RADIUS,
+        };
+        !self
+            .asteroids
+            .iter()
+            .any(|asteroid| asteroid.collides_with(&area, self.bounds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Difficulty, GameEvent, GameState, Mode, Rules, TIMESTEP};
+    use crate::game::models::player::Actions;
+    use crate::game::models::testing::BOUNDS;
+    use crate::game::models::Positioned;
+
+    /// Inputs that keep changing over the course of a game, turning, thrusting,
+    /// shooting and switching weapons every now and then.
+    fn scripted_actions(tick: u64) -> Actions {
+        let second = tick / 120;
+        Actions {
+            rotate_cw: second.is_multiple_of(4),
+            rotate_ccw: second % 4 == 2,
+            fire_boosters: second % 5 == 1,
+            fire_rev_boosters: second % 7 == 3,
+            is_shooting: tick % 90 < 60,
+            hyperspace: second % 11 == 10,
+            switch_weapon: second % 6 == 5,
+        }
+    }
+
+    /// Steps `state` until the game is over, giving up after `max_steps`.
+    fn play_until_game_over(state: &mut GameState, actions: &Actions, max_steps: u32) {
+        for _ in 0..max_steps {
+            if state.game_over() {
+                return;
+            }
+            state.step(TIMESTEP, actions);
+        }
+    }
+
+    #[test]
+    fn stepping_after_game_over_changes_nothing() {
+        let rules = Rules {
+            lives: 1,
+            ..Rules::new()
+        };
+        let mut state = GameState::new(BOUNDS, 7, rules);
+
+        // Jumping through hyperspace over and over soon fails and destroys the ship.
+        let actions = Actions {
+            hyperspace: true,
+            ..Actions::default()
+        };
+        play_until_game_over(&mut state, &actions, 1_000_000);
+        assert!(state.game_over());
+        assert_eq!(state.lives(), 0);
+
+        let score = state.score();
+        for _ in 0..1000 {
+            assert_eq!(state.step(TIMESTEP, &actions), Vec::<GameEvent>::new());
+        }
+        assert_eq!(state.lives(), 0);
+        assert_eq!(state.score(), score);
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_out_the_same() {
+        let play = || {
+            let mut state = GameState::new(BOUNDS, 1234, Rules::new());
+            let mut events = Vec::new();
+            for tick in 0..120 * 60 {
+                events.extend(state.step(TIMESTEP, &scripted_actions(tick)));
+            }
+            let positions: Vec<_> = state
+                .asteroids()
+                .iter()
+                .map(|asteroid| (asteroid.x(), asteroid.y()))
+                .collect();
+            (state.score(), state.lives(), events, positions)
+        };
+        let first = play();
+        assert!(first.0 > 0, "the scripted inputs should score some points");
+        assert_eq!(first, play());
+    }
+
+    #[test]
+    fn long_games_keep_running() {
+        for mode in Mode::ALL {
+            for difficulty in Difficulty::ALL {
+                let rules = Rules {
+                    mode,
+                    difficulty,
+                    lives: 9,
+                    ..Rules::new()
+                };
+                let mut state = GameState::new(BOUNDS, 99, rules);
+                let mut explosions = 0;
+                for tick in 0..120 * 60 * 3 {
+                    let events = state.step(TIMESTEP, &scripted_actions(tick));
+                    explosions += events
+                        .iter()
+                        .filter(|&&event| event == GameEvent::AsteroidExplosion)
+                        .count();
+                }
+                if let Some(player) = state.player() {
+                    assert!((0.0..BOUNDS.width).contains(&player.x()));
+                    assert!((0.0..BOUNDS.height).contains(&player.y()));
+                }
+                assert!(
+                    explosions > 0,
+                    "no asteroids destroyed in {mode:?} {difficulty:?}"
+                );
+            }
+        }
+    }
+}
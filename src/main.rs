@@ -1,34 +1,180 @@
 //! Rust belt is a 2D video game inspired by Asteroids.
 //! It runs atop the Piston game engine for graphics and SDL2 for sound.
 
-use opengl_graphics::GlGraphics;
-use piston_window::{OpenGL, PistonWindow, Size, WindowSettings};
+use std::env;
+use std::path::PathBuf;
 
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston_window::{Size, TextureSettings};
+
+use crate::assets::Assets;
+use crate::audio::{AudioSink, Music, NullAudio, SdlAudio, Sound};
+use crate::config::Config;
+use crate::screen::{Resources, ScreenManager};
+use crate::video::Video;
+
+mod assets;
+mod audio;
+mod config;
 mod game;
 mod menu;
+mod replays;
+mod screen;
 mod settings;
 mod story;
+mod video;
+
+const GAME_TITLE: &str = "Rust Belt";
+
+/// The size screens are laid out in. It is scaled to fit the window.
+const GAME_WINDOW_SIZE: Size = Size {
+    width: 1024.0,
+    height: 768.0,
+};
+
+/// Options that can be given on the command line.
+#[derive(Default)]
+struct Args {
+    /// Seed for the gameplay random number generator, so that a game can be replayed exactly.
+    seed: Option<u64>,
+
+    /// Run without sound, even if an audio device is available.
+    mute: bool,
+
+    /// Directory to load assets from instead of the default locations.
+    assets: Option<PathBuf>,
+}
+
+/// Parses the command line arguments, exiting with a usage message if they are invalid.
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--seed" => {
+                let seed = arguments.next().and_then(|value| value.parse().ok());
+                if seed.is_none() {
+                    exit_with_usage("--seed requires an unsigned integer value");
+                }
+                args.seed = seed;
+            }
+            "--mute" => args.mute = true,
+            "--assets" => match arguments.next() {
+                Some(root) => args.assets = Some(PathBuf::from(root)),
+                None => exit_with_usage("--assets requires a directory"),
+            },
+            _ => exit_with_usage(&format!("unknown argument '{argument}'")),
+        }
+    }
+    args
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("error: {message}");
+    eprintln!("usage: rust-belt [--seed <u64>] [--mute] [--assets <dir>]");
+    std::process::exit(2);
+}
 
-/// Creates a new window and runs the game starts the main menu.
+/// Starts the audio, then runs the game.
 fn main() {
-    let game_title = "Rust Belt";
-    let game_window_size = Size {
-        width: 1024.0,
-        height: 768.0,
-    };
-
-    let opengl = OpenGL::V3_2;
-
-    let mut window: PistonWindow = WindowSettings::new(
-        game_title,
-        [game_window_size.width, game_window_size.height],
-    )
-    .samples(4)
-    .exit_on_esc(true)
-    .build()
-    .unwrap_or_else(|error| panic!("Failed to build PistonWindow: {error}"));
-
-    let mut gl = GlGraphics::new(opengl);
-
-    menu::run(&mut window, &mut gl, game_window_size);
+    let args = parse_args();
+
+    let assets = Assets::locate(args.assets.clone());
+    if let Err(missing) = assets.verify() {
+        eprintln!("error: missing assets in '{}':", assets.root().display());
+        for path in missing {
+            eprintln!("    {}", path.display());
+        }
+        eprintln!(
+            "Use --assets <dir> or set {} to the directory containing the game's assets.",
+            assets::ROOT_ENV_VAR
+        );
+        std::process::exit(1);
+    }
+
+    if args.mute {
+        run(&args, assets, Box::new(NullAudio));
+        return;
+    }
+
+    // Carry on silently rather than crashing on machines without an audio device.
+    match sdl2::init().and_then(|sdl| sdl.audio().map(|_| sdl)) {
+        Ok(sdl) => music::start_context::<Music, Sound, _>(&sdl, SdlAudio::SOUND_CHANNELS, || {
+            let audio = Box::new(SdlAudio::new(&assets));
+            run(&args, assets, audio)
+        }),
+        Err(error) => {
+            eprintln!("Audio is unavailable, continuing without sound: {error}");
+            run(&args, assets, Box::new(NullAudio));
+        }
+    }
+}
+
+fn load_font(assets: &Assets) -> GlyphCache<'static> {
+    GlyphCache::new(assets.path(assets::FONT), (), TextureSettings::new()).unwrap()
+}
+
+/// Creates the window and runs the screens, starting with the main menu, until the
+/// last screen is closed. The window is rebuilt whenever the video settings change.
+fn run(args: &Args, assets: Assets, audio: Box<dyn AudioSink>) {
+    let mut config = Config::load();
+
+    // Saved video settings might not work on this machine any more, such as after
+    // changing the graphics card, so fall back to the defaults.
+    let mut window = config
+        .video
+        .build_window(GAME_TITLE)
+        .unwrap_or_else(|error| {
+            eprintln!("Failed to apply saved video settings, using the defaults: {error}");
+            config.video = Video::new();
+            config
+                .video
+                .build_window(GAME_TITLE)
+                .unwrap_or_else(|error| panic!("Failed to build PistonWindow: {error}"))
+        });
+    let mut gl = GlGraphics::new(video::OPENGL);
+
+    // The video settings the current window was built with.
+    let mut applied = config.video;
+
+    let menu = menu::Menu::new(GAME_WINDOW_SIZE, &assets);
+    let mut resources = Resources::new(
+        load_font(&assets),
+        config,
+        GAME_WINDOW_SIZE,
+        args.seed,
+        assets,
+        audio,
+    );
+    let mut screens = ScreenManager::new(Box::new(menu), &mut resources);
+    while let Some(event) = window.next() {
+        screens.event(&event, &mut gl, &mut resources);
+        if screens.is_empty() {
+            break;
+        }
+
+        if resources.take_video_change() {
+            let video = resources.video();
+            if !video.needs_new_window(&applied) {
+                video.apply_rates(&mut window);
+            } else {
+                match video.build_window(GAME_TITLE) {
+                    Ok(new_window) => {
+                        // Everything tied to the old window's OpenGL context has to be recreated.
+                        window = new_window;
+                        gl = GlGraphics::new(video::OPENGL);
+                        resources.glyph_cache = load_font(&resources.assets);
+                        screens.reload(&mut resources);
+                    }
+                    Err(error) => {
+                        eprintln!("Failed to apply video settings: {error}");
+                        resources.set_video(applied);
+                        resources.save_settings();
+                        continue;
+                    }
+                }
+            }
+            applied = video;
+        }
+    }
 }
@@ -8,74 +8,29 @@ use ai_behavior::{Action, Sequence};
 
 use opengl_graphics::{GlGraphics, GlyphCache, Texture};
 use piston_window::{
-    clear, text, Button, Context, Key, PistonWindow, PressEvent, RenderEvent, Size,
-    TextureSettings, Transformed, UpdateEvent,
+    clear, text, Button, ButtonArgs, ButtonState, Context, Event, Key, Loop, Size, TextureSettings,
+    Transformed, UpdateArgs,
 };
 use sprite::{Ease, EaseFunction, FadeIn, Scene, Sprite};
 
+use crate::assets::{self, Assets};
+use crate::audio::{Music, Sound};
 use crate::game;
 use crate::game::color::{self, ColoredText};
+use crate::replays;
+use crate::screen::{Resources, Screen, Transition};
 use crate::settings;
 use crate::story;
 
-/// The different music soundtrack pieces in the game.
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
-enum Music {
-    /// Menu soundtrack.
-    Menu,
-
-    /// Action soundtrack while playing the actual game.
-    Action,
-
-    /// Game over soundtrack.
-    GameOver,
-}
-
-/// Sound effects.
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
-pub enum Sound {
-    MenuSelection,
-    MenuBack,
-    MenuValidate,
-    WeaponShoot,
-    AsteroidExplosion,
-}
-
-/// Volume for music and sound effects.
-#[derive(Copy, Clone)]
-pub struct Volume {
-    pub music: f64,
-    pub sound: f64,
-}
-
-impl Volume {
-    pub fn new() -> Self {
-        Volume {
-            music: music::MAX_VOLUME,
-            sound: music::MAX_VOLUME,
-        }
-    }
-}
-
-/// Binds sound and music files to enums to be used with piston-music.
-fn bind_sound_files() {
-    music::bind_music_file(Music::Menu, "./assets/music/The Last Ranger.mp3");
-    music::bind_music_file(Music::Action, "./assets/music/Into the Field.mp3");
-    music::bind_music_file(Music::GameOver, "./assets/music/Splintered Glass.mp3");
-
-    music::bind_sound_file(Sound::MenuSelection, "./assets/sfx/menu-select.wav");
-    music::bind_sound_file(Sound::MenuBack, "./assets/sfx/menu-back.wav");
-    music::bind_sound_file(Sound::MenuValidate, "./assets/sfx/menu-validate.wav");
-    music::bind_sound_file(Sound::WeaponShoot, "./assets/sfx/weapon.wav");
-    music::bind_sound_file(Sound::AsteroidExplosion, "./assets/sfx/small-explosion.wav");
-}
-
 /// The currently selected menu item the user is highlighting.
 #[derive(Copy, Clone)]
 enum MenuSelection {
     /// Start playing the game.
     Play,
 
+    /// Watch a recorded game.
+    Replays,
+
     /// Display the introduction story dialogue.
     Story,
 
@@ -100,11 +55,13 @@ fn draw(
 
     // Color all menu items the same unless it is currently selected.
     let mut play_color = color::WHITE;
+    let mut replays_color = color::WHITE;
     let mut story_color = color::WHITE;
     let mut settings_color = color::WHITE;
     let mut exit_color = color::WHITE;
     match menu_selection {
         MenuSelection::Play => play_color = color::YELLOW,
+        MenuSelection::Replays => replays_color = color::YELLOW,
         MenuSelection::Story => story_color = color::YELLOW,
         MenuSelection::Settings => settings_color = color::YELLOW,
         MenuSelection::Exit => exit_color = color::YELLOW,
@@ -115,6 +72,10 @@ fn draw(
             color: play_color,
             text: "Play",
         },
+        ColoredText {
+            color: replays_color,
+            text: "Replays",
+        },
         ColoredText {
             color: story_color,
             text: "Story",
@@ -149,15 +110,10 @@ fn draw(
 }
 
 /// Create an animated fade in sprite of the game logo.
-fn create_logo_scene(window_size: Size) -> Scene<Texture> {
+fn create_logo_scene(window_size: Size, assets: &Assets) -> Scene<Texture> {
     let mut scene = Scene::new();
-    let tex = Rc::new(
-        Texture::from_path(
-            "./assets/images/rust-belt-logo-transparent.png",
-            &TextureSettings::new(),
-        )
-        .unwrap(),
-    );
+    let tex =
+        Rc::new(Texture::from_path(assets.path(assets::LOGO), &TextureSettings::new()).unwrap());
     let mut sprite = Sprite::from_texture(Rc::clone(&tex));
     sprite.set_position(window_size.width / 2.0, window_size.height / 2.0 - 120.0);
     sprite.set_scale(0.4, 0.4);
@@ -172,99 +128,99 @@ fn create_logo_scene(window_size: Size) -> Scene<Texture> {
     scene
 }
 
-/// Loops the menu screen, taking user input to change the current menu selection.
-pub fn run(window: &mut PistonWindow, opengl: &mut GlGraphics, window_size: Size) {
-    music::start::<Music, Sound, _>(32, || {
-        bind_sound_files();
-
-        let mut logo_scene = create_logo_scene(window_size);
+/// The main menu screen.
+pub struct Menu {
+    logo_scene: Scene<Texture>,
+    menu_selection: MenuSelection,
+    menu_align: f64,
+}
 
-        // The glyph cache is mutable because it loads each character on demand (lazily),
-        // and thus must be able to be changed over time as new characters are requested.
-        let mut glyph_cache = GlyphCache::new(
-            "./assets/fonts/FiraSans-Regular.ttf",
-            (),
-            TextureSettings::new(),
-        )
-        .unwrap();
+impl Menu {
+    pub fn new(window_size: Size, assets: &Assets) -> Self {
+        Menu {
+            logo_scene: create_logo_scene(window_size, assets),
+            menu_selection: MenuSelection::Play,
+            menu_align: window_size.width / 2.0 - 120.0,
+        }
+    }
+}
 
-        let mut volume = Volume::new();
-        volume.sound = 0.50;
-        music::set_volume(volume.music);
-        music::play_music(&Music::Menu, music::Repeat::Forever);
+impl Screen for Menu {
+    fn enter(&mut self, resources: &mut Resources) {
+        resources.play_music(Music::Menu);
+    }
 
-        let mut menu_selection = MenuSelection::Play;
-        let menu_align = window_size.width / 2.0 - 120.0;
-        while let Some(event) = window.next() {
-            if let Some(args) = event.render_args() {
-                opengl.draw(args.viewport(), |context, graphics| {
-                    clear(color::BLACK, graphics);
-                    draw(
-                        context,
-                        graphics,
-                        &mut glyph_cache,
-                        menu_align,
-                        menu_selection,
-                        &logo_scene,
-                    );
-                });
-            }
+    fn update(&mut self, _resources: &mut Resources, args: UpdateArgs) -> Transition {
+        self.logo_scene.event(&Event::Loop(Loop::Update(args)));
+        Transition::None
+    }
 
-            if event.update_args().is_some() {
-                logo_scene.event(&event);
-            }
+    fn reload(&mut self, resources: &mut Resources) {
+        self.logo_scene = create_logo_scene(resources.window_size, &resources.assets);
+    }
 
-            if let Some(Button::Keyboard(key)) = event.press_args() {
-                music::play_sound(&Sound::MenuSelection, music::Repeat::Times(0), volume.sound);
-                match key {
-                    Key::W => match menu_selection {
-                        MenuSelection::Play => {}
-                        MenuSelection::Story => menu_selection = MenuSelection::Play,
-                        MenuSelection::Settings => menu_selection = MenuSelection::Story,
-                        MenuSelection::Exit => menu_selection = MenuSelection::Settings,
-                    },
-                    Key::S => match menu_selection {
-                        MenuSelection::Play => menu_selection = MenuSelection::Story,
-                        MenuSelection::Story => menu_selection = MenuSelection::Settings,
-                        MenuSelection::Settings => menu_selection = MenuSelection::Exit,
-                        MenuSelection::Exit => {}
-                    },
-                    Key::Space => {
-                        music::play_sound(
-                            &Sound::MenuValidate,
-                            music::Repeat::Times(0),
-                            volume.sound,
-                        );
-                        match menu_selection {
-                            MenuSelection::Play => {
-                                music::play_music(&Music::Action, music::Repeat::Forever);
-                                let mut game = game::Game::new(window_size, volume);
-                                game.run(window, opengl, &mut glyph_cache);
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        clear(color::BLACK, graphics);
+        draw(
+            context,
+            graphics,
+            &mut resources.glyph_cache,
+            self.menu_align,
+            self.menu_selection,
+            &self.logo_scene,
+        );
+    }
 
-                                if game.game_over() {
-                                    music::play_music(&Music::GameOver, music::Repeat::Forever);
-                                    game.run_game_over(window, opengl, &mut glyph_cache);
-                                }
-                                music::play_music(&Music::Menu, music::Repeat::Forever);
-                            }
-                            MenuSelection::Story => {
-                                story::run(window, opengl, &mut glyph_cache, volume);
-                            }
-                            MenuSelection::Settings => {
-                                settings::run(
-                                    window,
-                                    opengl,
-                                    &mut glyph_cache,
-                                    &mut volume,
-                                    menu_align,
-                                );
-                            }
-                            MenuSelection::Exit => break,
-                        }
+    /// Takes user input to change the current menu selection.
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition {
+        let key = match input {
+            ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Keyboard(key),
+                ..
+            } => key,
+            _ => return Transition::None,
+        };
+
+        resources.play_sound(Sound::MenuSelection);
+        match key {
+            Key::W => match self.menu_selection {
+                MenuSelection::Play => {}
+                MenuSelection::Replays => self.menu_selection = MenuSelection::Play,
+                MenuSelection::Story => self.menu_selection = MenuSelection::Replays,
+                MenuSelection::Settings => self.menu_selection = MenuSelection::Story,
+                MenuSelection::Exit => self.menu_selection = MenuSelection::Settings,
+            },
+            Key::S => match self.menu_selection {
+                MenuSelection::Play => self.menu_selection = MenuSelection::Replays,
+                MenuSelection::Replays => self.menu_selection = MenuSelection::Story,
+                MenuSelection::Story => self.menu_selection = MenuSelection::Settings,
+                MenuSelection::Settings => self.menu_selection = MenuSelection::Exit,
+                MenuSelection::Exit => {}
+            },
+            Key::Space => {
+                resources.play_sound(Sound::MenuValidate);
+                return match self.menu_selection {
+                    MenuSelection::Play => {
+                        let seed = resources.seed.unwrap_or_else(rand::random);
+                        Transition::Push(Box::new(game::Game::new(
+                            resources.window_size,
+                            seed,
+                            resources.rules,
+                        )))
+                    }
+                    MenuSelection::Replays => {
+                        Transition::Push(Box::new(replays::Replays::new(self.menu_align)))
                     }
-                    _ => {}
-                }
+                    MenuSelection::Story => Transition::Push(Box::new(story::Story)),
+                    MenuSelection::Settings => {
+                        Transition::Push(Box::new(settings::Settings::new(self.menu_align)))
+                    }
+                    MenuSelection::Exit => Transition::Quit,
+                };
             }
+            _ => {}
         }
-    });
+        Transition::None
+    }
 }
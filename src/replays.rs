@@ -0,0 +1,164 @@
+//! Browse and watch recorded games.
+
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston_window::{clear, text, Button, ButtonArgs, ButtonState, Context, Key, Transformed};
+
+use crate::audio::{Music, Sound};
+use crate::game::color;
+use crate::game::replay::Replay;
+use crate::game::state::TIMESTEP;
+use crate::game::Game;
+use crate::screen::{Resources, Screen, Transition};
+
+/// The most replays listed at once, newest first.
+const MAX_LISTED: usize = 10;
+
+fn draw(
+    context: Context,
+    graphics: &mut GlGraphics,
+    glyph_cache: &mut GlyphCache<'_>,
+    replays: &[Replay],
+    selected: usize,
+    left_alignment: f64,
+) {
+    let menu_font = 32;
+    let starting_line_offset = 200.0;
+    let new_line_offset = 40.0;
+
+    clear(color::BLACK, graphics);
+
+    if replays.is_empty() {
+        text(
+            color::WHITE,
+            menu_font,
+            "No replays recorded yet",
+            glyph_cache,
+            context
+                .transform
+                .trans(left_alignment, starting_line_offset),
+            graphics,
+        )
+        .unwrap();
+        return;
+    }
+
+    for (index, replay) in replays.iter().enumerate() {
+        // Replays recorded with another version of the simulation can not be played back.
+        let line_color = if !replay.is_compatible() {
+            color::GRAY
+        } else if index == selected {
+            color::YELLOW
+        } else {
+            color::WHITE
+        };
+        let mut line = format!(
+            "Seed {}  {:.0}s",
+            replay.seed(),
+            replay.length() as f64 * TIMESTEP
+        );
+        if !replay.is_compatible() {
+            line.push_str(&format!("  (v{})", replay.version()));
+        }
+
+        text(
+            line_color,
+            menu_font,
+            &line,
+            glyph_cache,
+            context.transform.trans(
+                left_alignment,
+                starting_line_offset + index as f64 * new_line_offset,
+            ),
+            graphics,
+        )
+        .unwrap();
+    }
+}
+
+/// Screen listing the saved replays until the user exits.
+/// Selecting a replay plays it back.
+pub struct Replays {
+    replays: Vec<Replay>,
+    selected: usize,
+    left_alignment: f64,
+}
+
+impl Replays {
+    pub fn new(left_alignment: f64) -> Self {
+        Replays {
+            replays: Vec::new(),
+            selected: 0,
+            left_alignment,
+        }
+    }
+}
+
+impl Screen for Replays {
+    /// Reloads the list every time it is shown, so newly recorded games appear.
+    /// Coming back from watching a replay also brings back the menu music.
+    fn enter(&mut self, resources: &mut Resources) {
+        resources.play_music(Music::Menu);
+        self.replays = Replay::list()
+            .iter()
+            .filter_map(|path| match Replay::load(path) {
+                Ok(replay) => Some(replay),
+                Err(error) => {
+                    eprintln!("Skipping replay {}: {error}", path.display());
+                    None
+                }
+            })
+            .take(MAX_LISTED)
+            .collect();
+        self.selected = self.selected.min(self.replays.len().saturating_sub(1));
+    }
+
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        draw(
+            context,
+            graphics,
+            &mut resources.glyph_cache,
+            &self.replays,
+            self.selected,
+            self.left_alignment,
+        )
+    }
+
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition {
+        let key = match input {
+            ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Keyboard(key),
+                ..
+            } => key,
+            _ => return Transition::None,
+        };
+
+        match key {
+            Key::W => {
+                resources.play_sound(Sound::MenuSelection);
+                self.selected = self.selected.saturating_sub(1);
+            }
+            Key::S => {
+                resources.play_sound(Sound::MenuSelection);
+                self.selected = (self.selected + 1).min(self.replays.len().saturating_sub(1));
+            }
+            Key::Space if !self.replays.is_empty() => {
+                if self.replays[self.selected].is_compatible() {
+                    resources.play_sound(Sound::MenuValidate);
+                    let replay = self.replays.remove(self.selected);
+                    return Transition::Push(Box::new(Game::from_replay(
+                        resources.window_size,
+                        replay,
+                    )));
+                }
+                resources.play_sound(Sound::MenuBack);
+            }
+            Key::Space | Key::X => {
+                resources.play_sound(Sound::MenuBack);
+                return Transition::Pop;
+            }
+            _ => {}
+        }
+        Transition::None
+    }
+}
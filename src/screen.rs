@@ -0,0 +1,294 @@
+//! Screens and the stack that drives them.
+//!
+//! Every part of the game that takes over the window (the menu, the story, the
+//! game itself, ...) is a `Screen`. Screens are kept on a stack by the
+//! `ScreenManager`, which feeds the events of the single window event loop to
+//! the screen on top. Screens change the stack by returning a `Transition`.
+//!
+//! Screens are laid out in a fixed logical size, `Resources::window_size`. The
+//! `ScreenManager` scales that to fit the actual window whenever it is resized,
+//! keeping its aspect ratio and filling the rest of the window with black bars.
+
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston_window::{
+    clear, rectangle, types, ButtonArgs, ButtonEvent, Context, Event, RenderEvent, Size,
+    Transformed, UpdateArgs, UpdateEvent,
+};
+
+use crate::assets::Assets;
+use crate::audio::{AudioSink, Music, Sound, Volume};
+use crate::config::Config;
+use crate::game::color;
+use crate::game::controls::Controls;
+use crate::game::state::Rules;
+use crate::video::Video;
+
+/// State that is shared between all screens.
+pub struct Resources {
+    /// The glyph cache is mutable because it loads each character on demand (lazily),
+    /// and thus must be able to be changed over time as new characters are requested.
+    pub glyph_cache: GlyphCache<'static>,
+    pub volume: Volume,
+    pub rules: Rules,
+    pub controls: Controls,
+
+    /// The logical size that screens are drawn in, regardless of the size of the window.
+    pub window_size: Size,
+
+    /// Seed to start every game with, if one was chosen on the command line.
+    pub seed: Option<u64>,
+    pub assets: Assets,
+
+    audio: Box<dyn AudioSink>,
+
+    /// The soundtrack currently playing.
+    music: Option<Music>,
+    video: Video,
+
+    /// Whether `video` changed since the window last applied it.
+    video_changed: bool,
+}
+
+impl Resources {
+    pub fn new(
+        glyph_cache: GlyphCache<'static>,
+        config: Config,
+        window_size: Size,
+        seed: Option<u64>,
+        assets: Assets,
+        mut audio: Box<dyn AudioSink>,
+    ) -> Self {
+        audio.set_music_volume(config.volume.music);
+        Resources {
+            glyph_cache,
+            volume: config.volume,
+            rules: config.rules,
+            controls: config.controls,
+            window_size,
+            seed,
+            assets,
+            audio,
+            music: None,
+            video: config.video,
+            video_changed: false,
+        }
+    }
+
+    /// Saves the current settings so they are kept the next time the game starts.
+    pub fn save_settings(&self) {
+        let config = Config {
+            volume: self.volume,
+            rules: self.rules,
+            video: self.video,
+            controls: self.controls,
+        };
+        if let Err(error) = config.save() {
+            eprintln!("Failed to save settings: {error}");
+        }
+    }
+
+    /// Plays a sound effect once at the current sound volume.
+    pub fn play_sound(&mut self, sound: Sound) {
+        self.audio.play_sound(sound, self.volume.sound);
+    }
+
+    /// Loops a soundtrack. The soundtrack is not restarted if it is already playing.
+    pub fn play_music(&mut self, track: Music) {
+        if self.music != Some(track) {
+            self.audio.play_music(track);
+            self.music = Some(track);
+        }
+    }
+
+    /// Applies a change to `volume.music` to the soundtrack that is playing.
+    pub fn update_music_volume(&mut self) {
+        self.audio.set_music_volume(self.volume.music);
+    }
+
+    pub fn video(&self) -> Video {
+        self.video
+    }
+
+    /// Changes the video settings. They are applied to the window once the current event
+    /// has been handled.
+    pub fn set_video(&mut self, video: Video) {
+        self.video = video;
+        self.video_changed = true;
+    }
+
+    /// Whether the video settings changed since this was last called.
+    pub fn take_video_change(&mut self) -> bool {
+        std::mem::replace(&mut self.video_changed, false)
+    }
+}
+
+/// What should happen to the screen stack after a screen handled an event.
+pub enum Transition {
+    /// Keep the current screen.
+    None,
+
+    /// Put a new screen on top of the current one.
+    Push(Box<dyn Screen>),
+
+    /// Remove the current screen, returning to the one below it.
+    Pop,
+
+    /// Swap the current screen for a new one.
+    Replace(Box<dyn Screen>),
+
+    /// Remove every screen, which exits the game.
+    Quit,
+}
+
+/// A part of the game that takes over the window.
+pub trait Screen {
+    /// Called when the screen becomes the top of the stack, either because it was
+    /// just added or because the screen above it was removed.
+    fn enter(&mut self, _resources: &mut Resources) {}
+
+    /// Advances the screen by `args.dt` seconds. Only the top screen is updated.
+    fn update(&mut self, _resources: &mut Resources, _args: UpdateArgs) -> Transition {
+        Transition::None
+    }
+
+    /// Draws the screen to the window.
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources);
+
+    /// Reacts to a button being pressed or released. Only the top screen receives input.
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition;
+
+    /// Called when the screen stops being the top of the stack, either because it
+    /// was removed or because another screen was added above it.
+    fn exit(&mut self, _resources: &mut Resources) {}
+
+    /// Called on every screen after the window has been rebuilt, which invalidates
+    /// any textures the screen created for the old window.
+    fn reload(&mut self, _resources: &mut Resources) {}
+
+    /// Overlays are drawn on top of the screen below them rather than replacing it.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// Drives a stack of screens from window events.
+pub struct ScreenManager {
+    screens: Vec<Box<dyn Screen>>,
+}
+
+impl ScreenManager {
+    pub fn new(mut first: Box<dyn Screen>, resources: &mut Resources) -> Self {
+        first.enter(resources);
+        ScreenManager {
+            screens: vec![first],
+        }
+    }
+
+    /// Whether every screen has been removed, meaning the game should exit.
+    pub fn is_empty(&self) -> bool {
+        self.screens.is_empty()
+    }
+
+    /// Lets every screen recreate its textures after the window has been rebuilt.
+    pub fn reload(&mut self, resources: &mut Resources) {
+        for screen in &mut self.screens {
+            screen.reload(resources);
+        }
+    }
+
+    /// Dispatches a single window event to the screens.
+    pub fn event(&mut self, event: &Event, opengl: &mut GlGraphics, resources: &mut Resources) {
+        if let Some(args) = event.render_args() {
+            // Draw the topmost opaque screen and every overlay above it.
+            let first_visible = self
+                .screens
+                .iter()
+                .rposition(|screen| !screen.is_overlay())
+                .unwrap_or(0);
+            let screens = &mut self.screens[first_visible..];
+            let window = Size::from(args.window_size);
+            let (view, scale) = letterbox(window, resources.window_size);
+            opengl.draw(args.viewport(), |context, graphics| {
+                clear(color::BLACK, graphics);
+                let scaled = context.trans(view[0], view[1]).scale(scale, scale);
+                for screen in screens {
+                    screen.draw(scaled, graphics, resources);
+                }
+
+                // Cover anything drawn outside of the view, such as the wrapped
+                // copies of objects crossing the edges of the playfield.
+                let bars = [
+                    [0.0, 0.0, view[0], window.height],
+                    [view[0] + view[2], 0.0, window.width, window.height],
+                    [0.0, 0.0, window.width, view[1]],
+                    [0.0, view[1] + view[3], window.width, window.height],
+                ];
+                for bar in bars {
+                    rectangle(color::BLACK, bar, context.transform, graphics);
+                }
+            });
+        }
+
+        if let Some(args) = event.update_args() {
+            if let Some(screen) = self.screens.last_mut() {
+                let transition = screen.update(resources, args);
+                self.apply(transition, resources);
+            }
+        }
+
+        if let Some(args) = event.button_args() {
+            if let Some(screen) = self.screens.last_mut() {
+                let transition = screen.handle_input(resources, args);
+                self.apply(transition, resources);
+            }
+        }
+    }
+
+    fn apply(&mut self, transition: Transition, resources: &mut Resources) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut screen) => {
+                if let Some(top) = self.screens.last_mut() {
+                    top.exit(resources);
+                }
+                screen.enter(resources);
+                self.screens.push(screen);
+            }
+            Transition::Pop => {
+                if let Some(mut top) = self.screens.pop() {
+                    top.exit(resources);
+                }
+                if let Some(top) = self.screens.last_mut() {
+                    top.enter(resources);
+                }
+            }
+            Transition::Replace(mut screen) => {
+                if let Some(mut top) = self.screens.pop() {
+                    top.exit(resources);
+                }
+                screen.enter(resources);
+                self.screens.push(screen);
+            }
+            Transition::Quit => {
+                while let Some(mut top) = self.screens.pop() {
+                    top.exit(resources);
+                }
+            }
+        }
+    }
+}
+
+/// Fits the logical size into the window as large as possible without changing its
+/// aspect ratio, centered in the window. Returns the area of the window it covers
+/// and how much it is scaled by.
+fn letterbox(window: Size, logical: Size) -> (types::Rectangle, f64) {
+    let scale = (window.width / logical.width).min(window.height / logical.height);
+    let (width, height) = (logical.width * scale, logical.height * scale);
+    let view = [
+        (window.width - width) / 2.0,
+        (window.height - height) / 2.0,
+        width,
+        height,
+    ];
+    (view, scale)
+}
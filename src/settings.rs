@@ -1,149 +1,280 @@
 //! Modify game settings.
 
 use opengl_graphics::{GlGraphics, GlyphCache};
-use piston_window::{
-    clear, text, Button, Context, Key, PistonWindow, PressEvent, RenderEvent, Transformed,
-};
+use piston_window::{clear, text, Button, ButtonArgs, ButtonState, Context, Key, Transformed};
 
+use crate::audio::{self, Sound, Volume};
 use crate::game::color;
-use crate::menu::{Sound, Volume};
+use crate::game::state::{self, Difficulty, Mode, Rules};
+use crate::screen::{Resources, Screen, Transition};
+use crate::video::{self, Video};
 
 /// The currently selected menu item the user is highlighting.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum MenuSelection {
     MusicVolume,
     SoundVolume,
+    Mode,
+    Difficulty,
+    Lives,
+    Fullscreen,
+    Resolution,
+    Vsync,
+    AntiAliasing,
+    FpsCap,
+    UpdateRate,
+}
+
+/// Every menu item, in the order they are listed.
+const MENU_ITEMS: [MenuSelection; 11] = [
+    MenuSelection::MusicVolume,
+    MenuSelection::SoundVolume,
+    MenuSelection::Mode,
+    MenuSelection::Difficulty,
+    MenuSelection::Lives,
+    MenuSelection::Fullscreen,
+    MenuSelection::Resolution,
+    MenuSelection::Vsync,
+    MenuSelection::AntiAliasing,
+    MenuSelection::FpsCap,
+    MenuSelection::UpdateRate,
+];
+
+impl MenuSelection {
+    fn label(self) -> &'static str {
+        match self {
+            MenuSelection::MusicVolume => "Music Volume",
+            MenuSelection::SoundVolume => "Sound Volume",
+            MenuSelection::Mode => "Mode",
+            MenuSelection::Difficulty => "Difficulty",
+            MenuSelection::Lives => "Lives",
+            MenuSelection::Fullscreen => "Fullscreen",
+            MenuSelection::Resolution => "Resolution",
+            MenuSelection::Vsync => "V-Sync",
+            MenuSelection::AntiAliasing => "Anti-Aliasing",
+            MenuSelection::FpsCap => "FPS Cap",
+            MenuSelection::UpdateRate => "Updates/Second",
+        }
+    }
+
+    fn value(self, volume: Volume, rules: Rules, video: Video) -> String {
+        let on_off = |enabled| String::from(if enabled { "On" } else { "Off" });
+        match self {
+            MenuSelection::MusicVolume => format!("{}%", (volume.music * 100.0) as i32),
+            MenuSelection::SoundVolume => format!("{}%", (volume.sound * 100.0) as i32),
+            MenuSelection::Mode => rules.mode.name().to_string(),
+            MenuSelection::Difficulty => rules.difficulty.name().to_string(),
+            MenuSelection::Lives => rules.lives.to_string(),
+            MenuSelection::Fullscreen => on_off(video.fullscreen),
+            MenuSelection::Resolution => {
+                format!("{}x{}", video.resolution[0], video.resolution[1])
+            }
+            MenuSelection::Vsync => on_off(video.vsync),
+            MenuSelection::AntiAliasing => match video.samples {
+                0 => on_off(false),
+                samples => format!("{samples}x"),
+            },
+            MenuSelection::FpsCap => video.max_fps.to_string(),
+            MenuSelection::UpdateRate => video.ups.to_string(),
+        }
+    }
+
+    fn is_video(self) -> bool {
+        !matches!(
+            self,
+            MenuSelection::MusicVolume
+                | MenuSelection::SoundVolume
+                | MenuSelection::Mode
+                | MenuSelection::Difficulty
+                | MenuSelection::Lives
+        )
+    }
+}
+
+/// Returns the option after (or before, if not `forward`) `current` in `options`,
+/// stopping at either end. Values that are not one of the options go to the first.
+fn step<T: Copy + PartialEq>(options: &[T], current: T, forward: bool) -> T {
+    match options.iter().position(|&option| option == current) {
+        Some(index) if forward => options[(index + 1).min(options.len() - 1)],
+        Some(index) => options[index.saturating_sub(1)],
+        None => options[0],
+    }
 }
 
 fn draw(
     context: Context,
     graphics: &mut GlGraphics,
-    glyph_cache: &mut GlyphCache<'_>,
+    resources: &mut Resources,
     menu_selection: MenuSelection,
-    volume: Volume,
     left_alignment: f64,
 ) {
-    // Color all menu items the same unless it is currently selected.
-    let mut music_color = color::WHITE;
-    let mut sound_color = color::WHITE;
-    match menu_selection {
-        MenuSelection::MusicVolume => music_color = color::YELLOW,
-        MenuSelection::SoundVolume => sound_color = color::YELLOW,
-    }
+    let (volume, rules, video) = (resources.volume, resources.rules, resources.video());
+    let glyph_cache: &mut GlyphCache<'_> = &mut resources.glyph_cache;
 
     let menu_font = 32;
-    let starting_line_offset = 280.0;
+    let starting_line_offset = 200.0;
     let value_left_alignment = left_alignment + 300.0;
     let new_line_offset = 40.0;
 
+    // Leave a gap between the audio and the video settings.
+    let section_offset = 30.0;
+
     clear(color::BLACK, graphics);
-    text(
-        music_color,
-        menu_font,
-        "Music Volume",
-        glyph_cache,
-        context
-            .transform
-            .trans(left_alignment, starting_line_offset),
-        graphics,
-    )
-    .unwrap();
-    text(
-        music_color,
-        menu_font,
-        &format!("{}%", (volume.music * 100.0) as i32),
-        glyph_cache,
-        context
-            .transform
-            .trans(value_left_alignment, starting_line_offset),
-        graphics,
-    )
-    .unwrap();
-    text(
-        sound_color,
-        menu_font,
-        "Sound Volume",
-        glyph_cache,
-        context
-            .transform
-            .trans(left_alignment, starting_line_offset + new_line_offset),
-        graphics,
-    )
-    .unwrap();
-    text(
-        sound_color,
-        menu_font,
-        &format!("{}%", (volume.sound * 100.0) as i32),
-        glyph_cache,
-        context
-            .transform
-            .trans(value_left_alignment, starting_line_offset + new_line_offset),
-        graphics,
-    )
-    .unwrap();
+    for (index, item) in MENU_ITEMS.iter().enumerate() {
+        // Color all menu items the same unless it is currently selected.
+        let item_color = if *item == menu_selection {
+            color::YELLOW
+        } else {
+            color::WHITE
+        };
+        let mut line_offset = starting_line_offset + index as f64 * new_line_offset;
+        if item.is_video() {
+            line_offset += section_offset;
+        }
+
+        text(
+            item_color,
+            menu_font,
+            item.label(),
+            glyph_cache,
+            context.transform.trans(left_alignment, line_offset),
+            graphics,
+        )
+        .unwrap();
+        text(
+            item_color,
+            menu_font,
+            &item.value(volume, rules, video),
+            glyph_cache,
+            context.transform.trans(value_left_alignment, line_offset),
+            graphics,
+        )
+        .unwrap();
+    }
 }
 
-/// Loop providing game setting options to change to the user until they exit the screen.
-pub fn run(
-    window: &mut PistonWindow,
-    opengl: &mut GlGraphics,
-    glyph_cache: &mut GlyphCache<'_>,
-    volume: &mut Volume,
+/// Screen providing game setting options to change to the user until they exit the screen.
+pub struct Settings {
+    menu_selection: MenuSelection,
     left_alignment: f64,
-) {
-    let mut menu_selection = MenuSelection::MusicVolume;
-
-    for event in window.by_ref() {
-        if let Some(args) = event.render_args() {
-            opengl.draw(args.viewport(), |context, graphics| {
-                draw(
-                    context,
-                    graphics,
-                    glyph_cache,
-                    menu_selection,
-                    *volume,
-                    left_alignment,
-                )
-            });
+}
+
+impl Settings {
+    pub fn new(left_alignment: f64) -> Self {
+        Settings {
+            menu_selection: MenuSelection::MusicVolume,
+            left_alignment,
         }
+    }
+
+    /// Moves the selection up or down the list, stopping at either end.
+    fn select(&mut self, forward: bool) {
+        self.menu_selection = step(&MENU_ITEMS, self.menu_selection, forward);
+    }
+
+    /// Changes the selected gameplay setting to its next (or previous) option.
+    fn change_rules(&self, resources: &mut Resources, forward: bool) {
+        let rules = &mut resources.rules;
+        match self.menu_selection {
+            MenuSelection::Mode => rules.mode = step(&Mode::ALL, rules.mode, forward),
+            MenuSelection::Difficulty => {
+                rules.difficulty = step(&Difficulty::ALL, rules.difficulty, forward)
+            }
+            MenuSelection::Lives => {
+                rules.lives = step(&state::STARTING_LIVES, rules.lives, forward)
+            }
+            _ => {}
+        }
+    }
+
+    /// Changes the selected video setting to its next (or previous) option.
+    fn change_video(&self, resources: &mut Resources, forward: bool) {
+        let mut video = resources.video();
+        match self.menu_selection {
+            MenuSelection::Fullscreen => video.fullscreen = !video.fullscreen,
+            MenuSelection::Resolution => {
+                video.resolution = step(video::RESOLUTIONS, video.resolution, forward)
+            }
+            MenuSelection::Vsync => video.vsync = !video.vsync,
+            MenuSelection::AntiAliasing => {
+                video.samples = step(video::SAMPLES, video.samples, forward)
+            }
+            MenuSelection::FpsCap => video.max_fps = step(video::FPS_CAPS, video.max_fps, forward),
+            MenuSelection::UpdateRate => video.ups = step(video::UPS_RATES, video.ups, forward),
+            MenuSelection::MusicVolume
+            | MenuSelection::SoundVolume
+            | MenuSelection::Mode
+            | MenuSelection::Difficulty
+            | MenuSelection::Lives => return,
+        }
+        if video != resources.video() {
+            resources.set_video(video);
+        }
+    }
+}
+
+impl Screen for Settings {
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        draw(
+            context,
+            graphics,
+            resources,
+            self.menu_selection,
+            self.left_alignment,
+        )
+    }
 
-        // TODO: Known precision problem related to stepping f64 instead of integers.
-        if let Some(Button::Keyboard(key)) = event.press_args() {
-            let volume_step: f64 = 0.1;
-
-            match key {
-                Key::W => match menu_selection {
-                    MenuSelection::MusicVolume => {}
-                    MenuSelection::SoundVolume => menu_selection = MenuSelection::MusicVolume,
-                },
-                Key::S => match menu_selection {
-                    MenuSelection::MusicVolume => menu_selection = MenuSelection::SoundVolume,
-                    MenuSelection::SoundVolume => {}
-                },
-                Key::D => {
-                    music::play_sound(&Sound::MenuSelection, music::Repeat::Times(0), volume.sound);
-                    match menu_selection {
-                        MenuSelection::MusicVolume => volume.music += volume_step,
-                        MenuSelection::SoundVolume => volume.sound += volume_step,
+    // TODO: Known precision problem related to stepping f64 instead of integers.
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition {
+        let key = match input {
+            ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Keyboard(key),
+                ..
+            } => key,
+            _ => return Transition::None,
+        };
+        let volume_step: f64 = 0.1;
+
+        match key {
+            Key::W => self.select(false),
+            Key::S => self.select(true),
+            Key::D => {
+                resources.play_sound(Sound::MenuSelection);
+                match self.menu_selection {
+                    MenuSelection::MusicVolume => resources.volume.music += volume_step,
+                    MenuSelection::SoundVolume => resources.volume.sound += volume_step,
+                    MenuSelection::Mode | MenuSelection::Difficulty | MenuSelection::Lives => {
+                        self.change_rules(resources, true)
                     }
+                    _ => self.change_video(resources, true),
                 }
-                Key::A => {
-                    music::play_sound(&Sound::MenuSelection, music::Repeat::Times(0), volume.sound);
-                    match menu_selection {
-                        MenuSelection::MusicVolume => volume.music -= volume_step,
-                        MenuSelection::SoundVolume => volume.sound -= volume_step,
+            }
+            Key::A => {
+                resources.play_sound(Sound::MenuSelection);
+                match self.menu_selection {
+                    MenuSelection::MusicVolume => resources.volume.music -= volume_step,
+                    MenuSelection::SoundVolume => resources.volume.sound -= volume_step,
+                    MenuSelection::Mode | MenuSelection::Difficulty | MenuSelection::Lives => {
+                        self.change_rules(resources, false)
                     }
+                    _ => self.change_video(resources, false),
                 }
-                Key::Space => {
-                    music::play_sound(&Sound::MenuBack, music::Repeat::Times(0), volume.sound);
-                    break;
-                }
-                _ => {}
             }
+            Key::Space => {
+                resources.play_sound(Sound::MenuBack);
+                return Transition::Pop;
+            }
+            _ => {}
+        }
 
-            volume.music = volume.music.max(music::MIN_VOLUME).min(music::MAX_VOLUME);
-            volume.sound = volume.sound.max(music::MIN_VOLUME).min(music::MAX_VOLUME);
-            music::set_volume(volume.music);
+        let volume = &mut resources.volume;
+        volume.music = volume.music.clamp(audio::MIN_VOLUME, audio::MAX_VOLUME);
+        volume.sound = volume.sound.clamp(audio::MIN_VOLUME, audio::MAX_VOLUME);
+        resources.update_music_volume();
+        if matches!(key, Key::A | Key::D) {
+            resources.save_settings();
         }
+        Transition::None
     }
 }
@@ -2,11 +2,12 @@
 
 use opengl_graphics::{GlGraphics, GlyphCache};
 use piston_window::{
-    clear, text, types, Button, Context, Key, PistonWindow, PressEvent, RenderEvent, Transformed,
+    clear, text, types, Button, ButtonArgs, ButtonState, Context, Key, Transformed,
 };
 
+use crate::audio::Sound;
 use crate::game::color::{self, ColoredText};
-use crate::menu::{Sound, Volume};
+use crate::screen::{Resources, Screen, Transition};
 
 fn draw(context: Context, graphics: &mut GlGraphics, glyph_cache: &mut GlyphCache<'_>) {
     const NARRATOR_COLOR: types::Color = color::WHITE;
@@ -130,25 +131,19 @@ fn draw(context: Context, graphics: &mut GlGraphics, glyph_cache: &mut GlyphCach
     }
 }
 
-/// Loop displaying the story until the user exits.
-pub fn run(
-    window: &mut PistonWindow,
-    opengl: &mut GlGraphics,
-    glyph_cache: &mut GlyphCache<'_>,
-    volume: Volume,
-) {
-    for event in window.by_ref() {
-        if let Some(args) = event.render_args() {
-            opengl.draw(args.viewport(), |context, graphics| {
-                draw(context, graphics, glyph_cache)
-            });
-        }
+/// Screen displaying the story until the user exits.
+pub struct Story;
+
+impl Screen for Story {
+    fn draw(&mut self, context: Context, graphics: &mut GlGraphics, resources: &mut Resources) {
+        draw(context, graphics, &mut resources.glyph_cache);
+    }
 
-        if let Some(Button::Keyboard(key)) = event.press_args() {
-            if key == Key::Space {
-                music::play_sound(&Sound::MenuBack, music::Repeat::Times(0), volume.sound);
-                break;
-            }
+    fn handle_input(&mut self, resources: &mut Resources, input: ButtonArgs) -> Transition {
+        if input.state == ButtonState::Press && input.button == Button::Keyboard(Key::Space) {
+            resources.play_sound(Sound::MenuBack);
+            return Transition::Pop;
         }
+        Transition::None
     }
 }
@@ -0,0 +1,87 @@
+//! Window and rendering options.
+//!
+//! Most options can only be chosen when the window is created, so changing them
+//! means building a new `PistonWindow`. The frame and update rates can be changed
+//! on the window that is already open.
+
+use std::error::Error;
+
+use piston_window::{EventLoop, OpenGL, PistonWindow, WindowSettings};
+
+/// The OpenGL version the window is created with.
+pub const OPENGL: OpenGL = OpenGL::V3_2;
+
+/// Window sizes that can be chosen when not in fullscreen.
+pub const RESOLUTIONS: &[[u32; 2]] = &[
+    [800, 600],
+    [1024, 768],
+    [1280, 720],
+    [1280, 960],
+    [1600, 900],
+    [1920, 1080],
+];
+
+/// Numbers of anti-aliasing samples that can be chosen, where 0 disables anti-aliasing.
+pub const SAMPLES: &[u8] = &[0, 2, 4, 8];
+
+/// Frame rate caps that can be chosen, in frames per second.
+pub const FPS_CAPS: &[u64] = &[30, 60, 120, 144, 240];
+
+/// Update rates that can be chosen, in updates per second.
+pub const UPS_RATES: &[u64] = &[60, 120, 240];
+
+/// Settings for the window and how often it is drawn and updated.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Video {
+    /// Whether the window covers the whole screen.
+    pub fullscreen: bool,
+
+    /// The size of the window when not in fullscreen.
+    pub resolution: [u32; 2],
+    pub vsync: bool,
+
+    /// Anti-aliasing samples per pixel.
+    pub samples: u8,
+    pub max_fps: u64,
+    pub ups: u64,
+}
+
+impl Video {
+    pub fn new() -> Self {
+        Video {
+            fullscreen: false,
+            resolution: [1024, 768],
+            vsync: false,
+            samples: 4,
+            max_fps: 60,
+            ups: 120,
+        }
+    }
+
+    /// Whether switching from `other` to these settings needs a new window.
+    pub fn needs_new_window(&self, other: &Video) -> bool {
+        self.fullscreen != other.fullscreen
+            || self.resolution != other.resolution
+            || self.vsync != other.vsync
+            || self.samples != other.samples
+    }
+
+    /// Creates a window using these settings.
+    pub fn build_window(&self, title: &str) -> Result<PistonWindow, Box<dyn Error>> {
+        let mut window: PistonWindow = WindowSettings::new(title, self.resolution)
+            .graphics_api(OPENGL)
+            .fullscreen(self.fullscreen)
+            .vsync(self.vsync)
+            .samples(self.samples)
+            .exit_on_esc(true)
+            .build()?;
+        self.apply_rates(&mut window);
+        Ok(window)
+    }
+
+    /// Applies the frame and update rates to a window that is already open.
+    pub fn apply_rates(&self, window: &mut PistonWindow) {
+        window.set_max_fps(self.max_fps);
+        window.set_ups(self.ups);
+    }
+}